@@ -0,0 +1,9 @@
+use http11::{App, Http11Server};
+
+// Run with `systemd-socket-activate -l 127.0.0.1:8080 <path to example>`
+// to serve on the socket passed through `LISTEN_FDS`.
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> std::io::Result<()> {
+    let server = Http11Server::builder().systemd_listeners()?.build();
+    server.serve(App::new()).await
+}
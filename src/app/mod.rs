@@ -1,10 +1,12 @@
 use crate::request::Request;
 use crate::response::Response;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
 type MiddleWare = dyn Fn(&Request, &Response) -> ();
 
-pub type SharedApp = Arc<Mutex<App>>;
+/// The app is only read while serving requests, so it is shared without a
+/// lock between connections and worker threads
+pub type SharedApp = Arc<App>;
 
 pub struct App {}
 
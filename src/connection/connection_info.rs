@@ -0,0 +1,74 @@
+use crate::connection::ProxyHeader;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Details of the TCP connection a request was received on
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConnectionInfo {
+    id: u64,
+    peer_addr: SocketAddr,
+    local_addr: SocketAddr,
+    proxy_header: Option<ProxyHeader>,
+}
+
+impl ConnectionInfo {
+    /// Creates the info of a newly accepted connection with an id unique
+    /// within the process
+    pub(crate) fn new(
+        peer_addr: SocketAddr,
+        local_addr: SocketAddr,
+    ) -> ConnectionInfo {
+        ConnectionInfo {
+            id: NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed),
+            peer_addr,
+            local_addr,
+            proxy_header: None,
+        }
+    }
+
+    /// Identifies the connection, shared by all requests received on it
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Address of the TCP peer. Behind a load balancer this is the balancer,
+    /// see [`ConnectionInfo::proxy_header`].
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.peer_addr
+    }
+
+    /// Address of the server socket which accepted the connection
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// The PROXY protocol header received on this connection, if the
+    /// listener was configured to expect one
+    pub fn proxy_header(&self) -> Option<&ProxyHeader> {
+        self.proxy_header.as_ref()
+    }
+
+    pub(crate) fn set_proxy_header(&mut self, proxy_header: ProxyHeader) {
+        self.proxy_header = Some(proxy_header);
+    }
+}
+
+#[cfg(test)]
+mod tests_connection_info {
+    use super::ConnectionInfo;
+
+    #[test]
+    fn test_unique_ids() {
+        let peer = "10.0.0.1:5000".parse().unwrap();
+        let local = "10.0.0.2:80".parse().unwrap();
+        let first = ConnectionInfo::new(peer, local);
+        let second = ConnectionInfo::new(peer, local);
+
+        assert_ne!(first.id(), second.id());
+        assert_eq!(first.peer_addr(), peer);
+        assert_eq!(first.local_addr(), local);
+        assert!(first.proxy_header().is_none());
+    }
+}
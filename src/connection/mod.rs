@@ -1,35 +1,106 @@
 use crate::app::{App, SharedApp};
-use crate::request::{Request, RequestBuilder};
+use crate::errors::Error as HttpError;
+use crate::request::{HttpMethods, Request, RequestBuilder};
 use crate::response::Response;
 use std::collections::linked_list::{IterMut as LinkedListIterMut, LinkedList};
 use std::io::{ErrorKind, Result};
+use std::mem;
+use std::time::Duration;
 use tokio::net::tcp::{ReadHalf, WriteHalf};
 use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+mod connection_info;
+mod proxy_protocol;
+pub use connection_info::*;
+pub use proxy_protocol::*;
 
 static FRAME_SIZE: usize = 1024;
 
+/// How long a peer has to send the PROXY protocol header
+const PROXY_HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+
 pub struct Connection {
     app: SharedApp,
     tcp_stream: TcpStream,
     requests: LinkedList<(Request, Response)>,
+    info: ConnectionInfo,
+    expects_proxy_header: bool,
 }
 
 impl Connection {
-    pub fn new(value: TcpStream, app: SharedApp) -> Self {
+    pub fn new(
+        value: TcpStream,
+        app: SharedApp,
+        info: ConnectionInfo,
+        expects_proxy_header: bool,
+    ) -> Self {
         let requests = LinkedList::new();
 
         Connection {
             tcp_stream: value,
             requests,
             app,
+            info,
+            expects_proxy_header,
+        }
+    }
+
+    /// Reads the PROXY protocol header which precedes the first request.
+    /// Returns the bytes received after the header, or `None` if the
+    /// connection should be closed.
+    async fn read_proxy_header(&mut self) -> Option<Vec<u8>> {
+        let mut received = Vec::with_capacity(FRAME_SIZE);
+        loop {
+            let stream = &mut self.tcp_stream;
+            stream.readable().await;
+            let mut buffer = vec![0; FRAME_SIZE];
+
+            match stream.try_read(&mut buffer) {
+                Ok(0) => return None,
+                Ok(n) => received.extend_from_slice(&buffer[..n]),
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
+                    continue;
+                }
+                Err(_) => return None,
+            };
+
+            match ProxyHeader::parse(&received) {
+                Ok(None) => continue,
+                Ok(Some((header, len))) => {
+                    self.info.set_proxy_header(header);
+                    return Some(received.split_off(len));
+                }
+                Err(_) => return None,
+            }
         }
     }
 
     pub async fn process_socket(&mut self) {
+        let mut initial_bytes = vec![];
+        if self.expects_proxy_header {
+            let header =
+                timeout(PROXY_HEADER_TIMEOUT, self.read_proxy_header()).await;
+            initial_bytes = match header {
+                Ok(Some(b)) => b,
+                // no valid header in time, the peer is not the proxy
+                Ok(None) | Err(_) => return,
+            };
+        }
+
         loop {
             let mut has_received_requests = false;
             let response = Response::new();
             let mut request_builder = RequestBuilder::new();
+            if !initial_bytes.is_empty() {
+                let buffer = mem::take(&mut initial_bytes);
+                let length = buffer.len();
+                if let Err(error) = request_builder.parse(buffer, length) {
+                    self.send_error(&error).await;
+                    return;
+                }
+            }
+
             loop {
                 let stream = &mut self.tcp_stream;
                 if !request_builder.can_parse_more() {
@@ -59,14 +130,9 @@ impl Connection {
 
                 println!("{}", String::from_utf8_lossy(&buffer[..]));
                 let result = request_builder.parse(buffer, bytes_read);
-                match result {
-                    Ok(_) => (),
-                    Err(error) => {
-                        // TODO
-                        // error while parsing
-                        // send appropriate response and
-                        // close the connection
-                    }
+                if let Err(error) = result {
+                    self.send_error(&error).await;
+                    return;
                 }
             }
 
@@ -75,36 +141,198 @@ impl Connection {
             }
 
             if !request_builder.can_parse_more() {
-                let request = request_builder.build();
-                self.app
-                    .as_ref()
-                    .lock()
-                    .unwrap()
-                    .process_request(&request, &response)
-                    .await;
+                let mut request = request_builder.build();
+                request.set_connection_info(self.info.clone());
+                self.app.process_request(&request, &response).await;
+                if self.send_response(&request, &response).await.is_err() {
+                    return;
+                }
             }
         }
     }
 
-    async fn send_response(&self, request: &Request, response: &Response) {
+    /// Writes the response to the socket. Fails if the connection has to
+    /// be closed.
+    async fn send_response(
+        &self,
+        request: &Request,
+        response: &Response,
+    ) -> Result<()> {
+        // the head of a HEAD response describes the body it leaves out
+        let is_head = request.method() == &HttpMethods::HEAD;
+        self.write_response(response, !is_head).await
+    }
+
+    /// Answers a request which could not be parsed with the status of
+    /// `error`. The connection has to be closed afterwards, as the rest of
+    /// the stream cannot be read as requests.
+    async fn send_error(&self, error: &HttpError) {
+        let mut response = Response::new();
+        response.set_status(error.status_code());
+        if response.headers_mut().insert("connection", "close").is_ok() {
+            // the connection is closed anyway
+            let _ = self.write_response(&response, true).await;
+        }
+    }
+
+    /// Writes the head and, if `with_body` and the status allows one, the
+    /// body of `response`
+    async fn write_response(
+        &self,
+        response: &Response,
+        with_body: bool,
+    ) -> Result<()> {
+        let mut buffer = vec![];
+        // TODO
+        // a handler set an invalid header, send a 500 instead
+        response.write_head(&mut buffer)?;
+
+        if with_body && response.status_allows_body() {
+            buffer.extend_from_slice(response.body());
+        }
+
         let stream = &self.tcp_stream;
-        loop {
+        let mut written = 0;
+        while written < buffer.len() {
             stream.writable().await;
             // Try to write data, this may still fail with `WouldBlock`
             // if the readiness event is a false positive.
-            match stream.try_write(
-                b"HTTP/1.1 200 Ok\r\ncontent-length: 3\r\n\r\nabc\r\n",
-            ) {
+            match stream.try_write(&buffer[written..]) {
                 Ok(n) => {
-                    break;
+                    written += n;
                 }
                 Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
                     continue;
                 }
-                Err(e) => {
-                    // return Err(e.into());
-                }
+                Err(e) => return Err(e),
             }
         }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests_connection {
+    use super::*;
+    use crate::app::App;
+    use crate::grammar::parse_http_date;
+    use crate::status::StatusCode;
+    use std::io::{Read, Write};
+    use std::sync::Arc;
+    use tokio::net::TcpListener;
+
+    /// Sends `request` on a new connection and returns what the server
+    /// wrote back
+    async fn exchange(request: &'static [u8]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let client = std::thread::spawn(move || {
+            let mut stream = std::net::TcpStream::connect(address).unwrap();
+            stream.write_all(request).unwrap();
+            stream.shutdown(std::net::Shutdown::Write).unwrap();
+
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        });
+
+        let (stream, peer_addr) = listener.accept().await.unwrap();
+        let info = ConnectionInfo::new(peer_addr, address);
+        let app = Arc::new(App::new());
+        Connection::new(stream, app, info, false)
+            .process_socket()
+            .await;
+
+        client.join().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_response_is_sent() {
+        let response = exchange(b"GET / HTTP/1.1\r\nHost: a\r\n\r\n").await;
+        let lines: Vec<&str> = response.split("\r\n").collect();
+        assert_eq!(lines[0], "HTTP/1.1 200 OK");
+        let date = lines[1].strip_prefix("date: ").unwrap();
+        assert!(parse_http_date(date).is_some());
+        assert_eq!(lines[2..], ["content-length: 0", "", ""]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_proxy_header_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        // the client sends nothing and waits for the server to close
+        let client = std::thread::spawn(move || {
+            let mut stream = std::net::TcpStream::connect(address).unwrap();
+            let mut response = vec![];
+            stream.read_to_end(&mut response).unwrap();
+            response
+        });
+
+        let (stream, peer_addr) = listener.accept().await.unwrap();
+        let info = ConnectionInfo::new(peer_addr, address);
+        let mut connection =
+            Connection::new(stream, Arc::new(App::new()), info, true);
+        connection.process_socket().await;
+        drop(connection);
+
+        assert!(client.join().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_bad_request() {
+        let requests: [&'static [u8]; 3] = [
+            b"GET /a b HTTP/1.1\r\nHost: a\r\n\r\n",
+            b"GET /?a=%zz HTTP/1.1\r\nHost: a\r\n\r\n",
+            b"POST / HTTP/1.1\r\nContent-Length: 1\r\n\
+              Content-Length: 2\r\n\r\nab",
+        ];
+
+        for request in requests {
+            let response = exchange(request).await;
+            let lines: Vec<&str> = response.split("\r\n").collect();
+            assert_eq!(lines[0], "HTTP/1.1 400 Bad Request", "{}", response);
+            assert!(lines.contains(&"connection: close"), "{}", response);
+        }
+    }
+
+    fn request(buffer: &[u8]) -> Request {
+        let mut builder = RequestBuilder::new();
+        builder.parse(buffer.to_vec(), buffer.len()).unwrap();
+        builder.build()
+    }
+
+    #[tokio::test]
+    async fn test_send_response_without_body() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let client = std::thread::spawn(move || {
+            let mut stream = std::net::TcpStream::connect(address).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        });
+
+        let (stream, peer_addr) = listener.accept().await.unwrap();
+        let info = ConnectionInfo::new(peer_addr, address);
+        let connection =
+            Connection::new(stream, Arc::new(App::new()), info, false);
+
+        let mut response = Response::new();
+        response.set_body(b"hello".to_vec());
+        let head = request(b"HEAD / HTTP/1.1\r\nHost: a\r\n\r\n");
+        connection.send_response(&head, &response).await.unwrap();
+
+        // a 304 to a GET has no body either
+        response.set_status(StatusCode::NOT_MODIFIED);
+        let get = request(b"GET / HTTP/1.1\r\nHost: a\r\n\r\n");
+        connection.send_response(&get, &response).await.unwrap();
+        drop(connection);
+
+        let response = client.join().unwrap();
+        assert!(!response.contains("hello"), "{}", response);
+        assert_eq!(response.matches("content-length: 5").count(), 2);
     }
 }
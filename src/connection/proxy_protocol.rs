@@ -0,0 +1,356 @@
+use crate::errors::Error as HttpError;
+use std::convert::TryInto;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str;
+
+/// The v1 header can never be longer than 107 bytes including the CRLF
+const V1_MAX_LENGTH: usize = 107;
+const V1_PREFIX: &[u8] = b"PROXY ";
+const V2_SIGNATURE: &[u8] = b"\r\n\r\n\0\r\nQUIT\n";
+/// signature + version/command + family/protocol + address length
+const V2_HEADER_LENGTH: usize = 16;
+
+/// Addresses of the original connection as reported by a load balancer
+/// speaking the HAProxy PROXY protocol (v1 or v2).
+///
+/// The addresses are `None` when the proxy did not relay them, i.e. for
+/// `PROXY UNKNOWN` in v1 and for the `LOCAL` command or unsupported address
+/// families in v2.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProxyHeader {
+    source: Option<SocketAddr>,
+    destination: Option<SocketAddr>,
+}
+
+impl ProxyHeader {
+    /// Address of the client which opened the connection to the proxy
+    pub fn source(&self) -> Option<SocketAddr> {
+        self.source
+    }
+
+    /// Address on which the proxy accepted the client connection
+    pub fn destination(&self) -> Option<SocketAddr> {
+        self.destination
+    }
+
+    /// Tries to parse a PROXY protocol header from the start of `buffer`.
+    ///
+    /// Returns `Ok(None)` if more bytes are needed, otherwise the parsed
+    /// header along with the number of bytes it occupied. The bytes after
+    /// that belong to the HTTP request.
+    pub(crate) fn parse(
+        buffer: &[u8],
+    ) -> Result<Option<(ProxyHeader, usize)>, HttpError> {
+        if is_prefix_of(buffer, V1_PREFIX) {
+            return ProxyHeader::parse_v1(buffer);
+        }
+
+        if is_prefix_of(buffer, V2_SIGNATURE) {
+            return ProxyHeader::parse_v2(buffer);
+        }
+
+        Err(HttpError::InvalidProxyHeader(
+            "connection does not start with a PROXY protocol signature".into(),
+        ))
+    }
+
+    fn parse_v1(
+        buffer: &[u8],
+    ) -> Result<Option<(ProxyHeader, usize)>, HttpError> {
+        let searched = &buffer[..buffer.len().min(V1_MAX_LENGTH)];
+        let line_end = searched.windows(2).position(|w| w == b"\r\n");
+        let line_end = match line_end {
+            Some(l) => l,
+            None if buffer.len() >= V1_MAX_LENGTH => {
+                return Err(HttpError::InvalidProxyHeader(
+                    "v1 header is longer than 107 bytes".into(),
+                ));
+            }
+            None => return Ok(None),
+        };
+
+        let line = str::from_utf8(&buffer[..line_end]).map_err(|_| {
+            HttpError::InvalidProxyHeader("v1 header is not ascii".into())
+        })?;
+
+        let parts: Vec<&str> = line.split(' ').collect();
+        let header = match parts[1] {
+            "UNKNOWN" => ProxyHeader {
+                source: None,
+                destination: None,
+            },
+            "TCP4" | "TCP6" if parts.len() == 6 => {
+                let is_v4 = parts[1] == "TCP4";
+                let source = parse_v1_address(parts[2], parts[4], is_v4)?;
+                let destination = parse_v1_address(parts[3], parts[5], is_v4)?;
+                ProxyHeader {
+                    source: Some(source),
+                    destination: Some(destination),
+                }
+            }
+            _ => {
+                return Err(HttpError::InvalidProxyHeader(format!(
+                    "malformed v1 header:- {}",
+                    line
+                )));
+            }
+        };
+
+        Ok(Some((header, line_end + 2)))
+    }
+
+    fn parse_v2(
+        buffer: &[u8],
+    ) -> Result<Option<(ProxyHeader, usize)>, HttpError> {
+        if buffer.len() < V2_HEADER_LENGTH {
+            return Ok(None);
+        }
+
+        let version_command = buffer[12];
+        if version_command >> 4 != 2 {
+            return Err(HttpError::InvalidProxyHeader(format!(
+                "unsupported version {}",
+                version_command >> 4
+            )));
+        }
+
+        let address_length = u16::from_be_bytes([buffer[14], buffer[15]]);
+        let total_length = V2_HEADER_LENGTH + address_length as usize;
+        if buffer.len() < total_length {
+            return Ok(None);
+        }
+
+        let addresses = &buffer[V2_HEADER_LENGTH..total_length];
+        let family = buffer[13] >> 4;
+        let header = match version_command & 0x0F {
+            // LOCAL: connection was opened by the proxy itself
+            0x0 => ProxyHeader {
+                source: None,
+                destination: None,
+            },
+            // PROXY
+            0x1 => match family {
+                0x1 if addresses.len() >= 12 => {
+                    let source: [u8; 4] = addresses[0..4].try_into().unwrap();
+                    let destination: [u8; 4] =
+                        addresses[4..8].try_into().unwrap();
+                    ProxyHeader {
+                        source: Some(socket_address(
+                            Ipv4Addr::from(source).into(),
+                            &addresses[8..10],
+                        )),
+                        destination: Some(socket_address(
+                            Ipv4Addr::from(destination).into(),
+                            &addresses[10..12],
+                        )),
+                    }
+                }
+                0x2 if addresses.len() >= 36 => {
+                    let source: [u8; 16] = addresses[0..16].try_into().unwrap();
+                    let destination: [u8; 16] =
+                        addresses[16..32].try_into().unwrap();
+                    ProxyHeader {
+                        source: Some(socket_address(
+                            Ipv6Addr::from(source).into(),
+                            &addresses[32..34],
+                        )),
+                        destination: Some(socket_address(
+                            Ipv6Addr::from(destination).into(),
+                            &addresses[34..36],
+                        )),
+                    }
+                }
+                0x1 | 0x2 => {
+                    return Err(HttpError::InvalidProxyHeader(
+                        "v2 address block is too short".into(),
+                    ));
+                }
+                // AF_UNSPEC and AF_UNIX carry no usable socket address
+                _ => ProxyHeader {
+                    source: None,
+                    destination: None,
+                },
+            },
+            command => {
+                return Err(HttpError::InvalidProxyHeader(format!(
+                    "unsupported v2 command {}",
+                    command
+                )));
+            }
+        };
+
+        Ok(Some((header, total_length)))
+    }
+}
+
+/// Returns true if `buffer` and `signature` agree on their common length
+fn is_prefix_of(buffer: &[u8], signature: &[u8]) -> bool {
+    let len = buffer.len().min(signature.len());
+    buffer[..len] == signature[..len]
+}
+
+fn parse_v1_address(
+    ip: &str,
+    port: &str,
+    is_v4: bool,
+) -> Result<SocketAddr, HttpError> {
+    let ip: IpAddr = ip.parse().map_err(|_| {
+        HttpError::InvalidProxyHeader(format!("invalid address:- {}", ip))
+    })?;
+
+    if ip.is_ipv4() != is_v4 {
+        return Err(HttpError::InvalidProxyHeader(format!(
+            "address does not match protocol:- {}",
+            ip
+        )));
+    }
+
+    let port: u16 = port.parse().map_err(|_| {
+        HttpError::InvalidProxyHeader(format!("invalid port:- {}", port))
+    })?;
+
+    Ok(SocketAddr::new(ip, port))
+}
+
+fn socket_address(ip: IpAddr, port: &[u8]) -> SocketAddr {
+    SocketAddr::new(ip, u16::from_be_bytes([port[0], port[1]]))
+}
+
+#[cfg(test)]
+mod tests_proxy_protocol {
+    use super::*;
+    use crate::assert_match_error;
+
+    #[test]
+    fn test_v1_tcp4() {
+        let buffer = b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 443\r\nGET /";
+        let (header, len) = ProxyHeader::parse(buffer).unwrap().unwrap();
+
+        assert_eq!(len, buffer.len() - 5);
+        assert_eq!(header.source(), Some("192.168.0.1:56324".parse().unwrap()));
+        assert_eq!(
+            header.destination(),
+            Some("192.168.0.11:443".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_v1_tcp6() {
+        let buffer = b"PROXY TCP6 ::1 2001:db8::1 1000 80\r\n";
+        let (header, _) = ProxyHeader::parse(buffer).unwrap().unwrap();
+
+        assert_eq!(header.source(), Some("[::1]:1000".parse().unwrap()));
+        assert_eq!(
+            header.destination(),
+            Some("[2001:db8::1]:80".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_v1_unknown() {
+        let buffer = b"PROXY UNKNOWN ffff::1 ffff::2 1 2\r\n";
+        let (header, len) = ProxyHeader::parse(buffer).unwrap().unwrap();
+
+        assert_eq!(len, buffer.len());
+        assert!(header.source().is_none());
+        assert!(header.destination().is_none());
+    }
+
+    #[test]
+    fn test_v1_incomplete() {
+        assert!(ProxyHeader::parse(b"PRO").unwrap().is_none());
+        assert!(ProxyHeader::parse(b"PROXY TCP4 1.1.1.1").unwrap().is_none());
+        assert!(ProxyHeader::parse(b"PROXY TCP4 1.1.1.1 2.2.2.2 1 2\r")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_v1_invalid() {
+        let result = ProxyHeader::parse(b"PROXY TCP4 ::1 ::2 1 2\r\n");
+        let expected_error = HttpError::InvalidProxyHeader(
+            "address does not match protocol:- ::1".into(),
+        );
+        assert_match_error!(result.err().unwrap(), expected_error);
+
+        let result = ProxyHeader::parse(b"PROXY TCP4 1.1.1.1 2.2.2.2 1\r\n");
+        assert!(result.is_err());
+
+        let too_long = [b'1'; 120];
+        let mut buffer = b"PROXY TCP4 ".to_vec();
+        buffer.extend_from_slice(&too_long);
+        assert!(ProxyHeader::parse(&buffer).is_err());
+    }
+
+    #[test]
+    fn test_no_proxy_header() {
+        let result = ProxyHeader::parse(b"GET / HTTP/1.1\r\n");
+        assert!(result.is_err());
+    }
+
+    fn v2_header(command: u8, family: u8, addresses: &[u8]) -> Vec<u8> {
+        let mut buffer = V2_SIGNATURE.to_vec();
+        buffer.push(0x20 | command);
+        buffer.push(family);
+        buffer.extend_from_slice(&(addresses.len() as u16).to_be_bytes());
+        buffer.extend_from_slice(addresses);
+        buffer
+    }
+
+    #[test]
+    fn test_v2_tcp4() {
+        let addresses = [10, 0, 0, 1, 10, 0, 0, 2, 0x1F, 0x90, 0x00, 0x50];
+        let mut buffer = v2_header(0x1, 0x11, &addresses);
+        let header_len = buffer.len();
+        buffer.extend_from_slice(b"GET / HTTP/1.1\r\n");
+
+        let (header, len) = ProxyHeader::parse(&buffer).unwrap().unwrap();
+        assert_eq!(len, header_len);
+        assert_eq!(header.source(), Some("10.0.0.1:8080".parse().unwrap()));
+        assert_eq!(header.destination(), Some("10.0.0.2:80".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_v2_tcp6_with_tlv() {
+        let mut addresses = vec![0; 32];
+        addresses[15] = 1;
+        addresses[31] = 2;
+        addresses.extend_from_slice(&[0x00, 0x01, 0x00, 0x02]);
+        // a PP2_TYPE_NOOP tlv which should be skipped
+        addresses.extend_from_slice(&[0x04, 0x00, 0x01, 0x00]);
+        let buffer = v2_header(0x1, 0x21, &addresses);
+
+        let (header, len) = ProxyHeader::parse(&buffer).unwrap().unwrap();
+        assert_eq!(len, buffer.len());
+        assert_eq!(header.source(), Some("[::1]:1".parse().unwrap()));
+        assert_eq!(header.destination(), Some("[::2]:2".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_v2_local() {
+        let buffer = v2_header(0x0, 0x00, &[]);
+        let (header, len) = ProxyHeader::parse(&buffer).unwrap().unwrap();
+
+        assert_eq!(len, 16);
+        assert!(header.source().is_none());
+    }
+
+    #[test]
+    fn test_v2_incomplete() {
+        let addresses = [10, 0, 0, 1, 10, 0, 0, 2, 0x1F, 0x90, 0x00, 0x50];
+        let buffer = v2_header(0x1, 0x11, &addresses);
+
+        for i in 0..buffer.len() {
+            assert!(ProxyHeader::parse(&buffer[..i]).unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn test_v2_invalid() {
+        let mut buffer = v2_header(0x1, 0x11, &[10, 0, 0, 1]);
+        assert!(ProxyHeader::parse(&buffer).is_err());
+
+        buffer[12] = 0x11;
+        assert!(ProxyHeader::parse(&buffer).is_err());
+    }
+}
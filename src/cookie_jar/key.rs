@@ -0,0 +1,125 @@
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+use std::fmt;
+
+const SIGNING_INFO: &[u8] = b"http11 cookie signing";
+const ENCRYPTION_INFO: &[u8] = b"http11 cookie encryption";
+
+/// Keys for signed and private cookies, derived from an application secret
+#[derive(Clone)]
+pub struct Key {
+    signing: [u8; 32],
+    encryption: [u8; 32],
+}
+
+impl Key {
+    /// Derives the keys from `secret`, which should come from a random
+    /// source and stay the same across restarts and servers.
+    ///
+    /// # Panics
+    ///
+    /// If `secret` is shorter than 32 bytes
+    pub fn derive_from(secret: &[u8]) -> Key {
+        assert!(
+            secret.len() >= 32,
+            "the cookie secret has to be at least 32 bytes long"
+        );
+
+        Key {
+            signing: derive(secret, SIGNING_INFO),
+            encryption: derive(secret, ENCRYPTION_INFO),
+        }
+    }
+
+    /// A random key. Cookies issued with it become invalid on restart.
+    pub fn generate() -> Key {
+        let mut secret = [0; 64];
+        rand::thread_rng().fill_bytes(&mut secret);
+        Key::derive_from(&secret)
+    }
+
+    pub(crate) fn signing(&self) -> &[u8] {
+        &self.signing
+    }
+
+    pub(crate) fn encryption(&self) -> &[u8] {
+        &self.encryption
+    }
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Key { .. }")
+    }
+}
+
+fn derive(secret: &[u8], info: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret)
+        .expect("HMAC accepts keys of any length");
+    mac.update(info);
+    mac.finalize().into_bytes().into()
+}
+
+/// The key new cookies are signed or encrypted with, and previous keys
+/// which are still accepted. Rotating the secret this way does not log out
+/// every user at once.
+#[derive(Clone, Debug)]
+pub struct KeyRing {
+    current: Key,
+    previous: Vec<Key>,
+}
+
+impl KeyRing {
+    pub fn new(current: Key) -> KeyRing {
+        KeyRing {
+            current,
+            previous: vec![],
+        }
+    }
+
+    /// Also accepts cookies issued with `key`
+    pub fn with_previous(mut self, key: Key) -> KeyRing {
+        self.previous.push(key);
+        self
+    }
+
+    pub(crate) fn current(&self) -> &Key {
+        &self.current
+    }
+
+    /// The current key followed by the previous ones
+    pub(crate) fn keys(&self) -> impl Iterator<Item = &Key> {
+        std::iter::once(&self.current).chain(self.previous.iter())
+    }
+}
+
+#[cfg(test)]
+mod tests_key {
+    use super::*;
+
+    #[test]
+    fn test_derive_from() {
+        let secret = [7; 32];
+        let a = Key::derive_from(&secret);
+        let b = Key::derive_from(&secret);
+        assert_eq!(a.signing(), b.signing());
+        assert_eq!(a.encryption(), b.encryption());
+        assert_ne!(a.signing(), a.encryption());
+
+        let c = Key::derive_from(&[8; 32]);
+        assert_ne!(a.signing(), c.signing());
+        assert_eq!(format!("{:?}", a), "Key { .. }");
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 32 bytes")]
+    fn test_short_secret() {
+        Key::derive_from(b"too short");
+    }
+
+    #[test]
+    fn test_generate() {
+        assert_ne!(Key::generate().signing(), Key::generate().signing());
+    }
+}
@@ -0,0 +1,222 @@
+use crate::headers::{Cookie, Headers, SetCookie, TypedHeader};
+use crate::request::Request;
+use crate::response::Response;
+
+#[cfg(feature = "secure-cookies")]
+mod key;
+#[cfg(feature = "secure-cookies")]
+mod private;
+#[cfg(feature = "secure-cookies")]
+mod signed;
+#[cfg(feature = "secure-cookies")]
+pub use key::{Key, KeyRing};
+#[cfg(feature = "secure-cookies")]
+pub use private::PrivateJar;
+#[cfg(feature = "secure-cookies")]
+pub use signed::SignedJar;
+
+/// The cookies sent with a request and the changes made to them while it is
+/// handled. [`CookieJar::apply`] turns the changes into `Set-Cookie`
+/// headers, so cookies which did not change are not sent back.
+#[derive(Clone, Debug, Default)]
+pub struct CookieJar {
+    original: Vec<(String, String)>,
+    delta: Vec<DeltaCookie>,
+}
+
+#[derive(Clone, Debug)]
+struct DeltaCookie {
+    cookie: SetCookie,
+    removed: bool,
+}
+
+impl CookieJar {
+    pub fn new() -> CookieJar {
+        CookieJar {
+            original: vec![],
+            delta: vec![],
+        }
+    }
+
+    pub fn from_request(request: &Request) -> CookieJar {
+        CookieJar::from_headers(request.headers())
+    }
+
+    /// Jar with the cookies of every `Cookie` header line in `headers`
+    pub fn from_headers(headers: &Headers) -> CookieJar {
+        let original = headers
+            .get_all(Cookie::NAME)
+            .into_iter()
+            .filter_map(|h| h.as_any().downcast_ref::<Cookie>())
+            .flat_map(|c| c.iter())
+            .map(|(n, v)| (n.to_string(), v.to_string()))
+            .collect();
+
+        CookieJar {
+            original,
+            delta: vec![],
+        }
+    }
+
+    /// Current value of the cookie `name`, taking changes into account
+    pub fn get(&self, name: &str) -> Option<&str> {
+        match self.delta.iter().find(|d| d.cookie.name() == name) {
+            Some(d) if d.removed => None,
+            Some(d) => Some(d.cookie.value()),
+            None => self
+                .original
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str()),
+        }
+    }
+
+    /// Adds `cookie`, replacing an earlier change to a cookie of that name
+    pub fn add(&mut self, cookie: SetCookie) {
+        self.delta.retain(|d| d.cookie.name() != cookie.name());
+        self.delta.push(DeltaCookie {
+            cookie,
+            removed: false,
+        });
+    }
+
+    /// Removes the cookie named like `cookie`. The user agent only deletes
+    /// it if `cookie` has the domain and path it was set with.
+    pub fn remove(&mut self, cookie: SetCookie) {
+        self.delta.retain(|d| d.cookie.name() != cookie.name());
+
+        // a cookie added while handling this request is just dropped
+        if self.original.iter().any(|(n, _)| n == cookie.name()) {
+            self.delta.push(DeltaCookie {
+                cookie: cookie.into_removal(),
+                removed: true,
+            });
+        }
+    }
+
+    /// Name/value pairs of the cookies after the changes
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        let original = self
+            .original
+            .iter()
+            .filter(move |(n, _)| {
+                !self.delta.iter().any(|d| d.cookie.name() == n)
+            })
+            .map(|(n, v)| (n.as_str(), v.as_str()));
+
+        let added = self
+            .delta
+            .iter()
+            .filter(|d| !d.removed)
+            .map(|d| (d.cookie.name(), d.cookie.value()));
+
+        original.chain(added)
+    }
+
+    /// The `Set-Cookie` headers needed for the changes
+    pub fn delta(&self) -> impl Iterator<Item = &SetCookie> {
+        self.delta.iter().map(|d| &d.cookie)
+    }
+
+    /// Adds a `Set-Cookie` header line to `response` for every change
+    pub fn apply(&self, response: &mut Response) {
+        for cookie in self.delta() {
+            response.add_cookie(cookie.clone());
+        }
+    }
+
+    /// View of the jar whose cookies are signed, so the client can read but
+    /// not modify them
+    #[cfg(feature = "secure-cookies")]
+    pub fn signed<'a>(&'a mut self, keys: &'a KeyRing) -> SignedJar<'a> {
+        SignedJar::new(self, keys)
+    }
+
+    /// View of the jar whose cookies are encrypted, so the client can
+    /// neither read nor modify them
+    #[cfg(feature = "secure-cookies")]
+    pub fn private<'a>(&'a mut self, keys: &'a KeyRing) -> PrivateJar<'a> {
+        PrivateJar::new(self, keys)
+    }
+}
+
+#[cfg(test)]
+mod tests_cookie_jar {
+    use super::*;
+    use std::convert::TryFrom;
+    use std::str;
+
+    fn jar(cookies: &str) -> CookieJar {
+        let headers = Headers::try_from(cookies.to_string()).unwrap();
+        CookieJar::from_headers(&headers)
+    }
+
+    fn cookie(name: &str, value: &str) -> SetCookie {
+        SetCookie::builder(name, value).path("/").build().unwrap()
+    }
+
+    #[test]
+    fn test_from_headers() {
+        let jar = jar("Cookie: a=1; b=2\r\nCookie: c=3");
+        let cookies: Vec<(&str, &str)> = jar.iter().collect();
+        assert_eq!(cookies, vec![("a", "1"), ("b", "2"), ("c", "3")]);
+        assert_eq!(jar.get("b"), Some("2"));
+        assert_eq!(jar.delta().count(), 0);
+    }
+
+    #[test]
+    fn test_add_and_remove() {
+        let mut jar = jar("Cookie: a=1; b=2");
+        jar.add(cookie("a", "10"));
+        jar.add(cookie("new", "x"));
+        jar.remove(cookie("b", ""));
+        assert_eq!(jar.get("a"), Some("10"));
+        assert_eq!(jar.get("b"), None);
+
+        let cookies: Vec<(&str, &str)> = jar.iter().collect();
+        assert_eq!(cookies, vec![("a", "10"), ("new", "x")]);
+
+        let delta: Vec<String> = jar.delta().map(|c| c.encode()).collect();
+        assert_eq!(
+            delta,
+            vec![
+                "a=10; Path=/",
+                "new=x; Path=/",
+                "b=; Expires=Thu, 01 Jan 1970 00:00:00 GMT; Max-Age=0; Path=/",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_remove_added_cookie() {
+        let mut jar = jar("Cookie: a=1");
+        jar.add(cookie("new", "x"));
+        jar.remove(cookie("new", ""));
+        assert_eq!(jar.get("new"), None);
+        assert_eq!(jar.delta().count(), 0);
+
+        jar.add(cookie("b", "2"));
+        jar.remove(cookie("a", ""));
+        jar.add(cookie("a", "3"));
+        let delta: Vec<String> = jar.delta().map(|c| c.encode()).collect();
+        assert_eq!(delta, vec!["b=2; Path=/", "a=3; Path=/"]);
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut jar = jar("Cookie: a=1");
+        jar.add(cookie("b", "2"));
+        jar.remove(cookie("a", ""));
+
+        let mut response = Response::new();
+        jar.apply(&mut response);
+
+        let mut buffer = vec![];
+        response.headers().write_to(&mut buffer).unwrap();
+        assert_eq!(
+            str::from_utf8(&buffer).unwrap(),
+            "set-cookie: b=2; Path=/\r\nset-cookie: a=; \
+             Expires=Thu, 01 Jan 1970 00:00:00 GMT; Max-Age=0; Path=/\r\n"
+        );
+    }
+}
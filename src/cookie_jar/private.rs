@@ -0,0 +1,142 @@
+use crate::cookie_jar::{CookieJar, Key, KeyRing};
+use crate::headers::SetCookie;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::RngCore;
+
+const NONCE_LEN: usize = 12;
+
+/// Cookies whose value is encrypted with AES-256-GCM under a random nonce.
+/// The cookie name is authenticated as well, so a value cannot be moved to
+/// another cookie. Cookies which do not decrypt are treated as absent.
+pub struct PrivateJar<'a> {
+    jar: &'a mut CookieJar,
+    keys: &'a KeyRing,
+}
+
+impl<'a> PrivateJar<'a> {
+    pub(crate) fn new(jar: &'a mut CookieJar, keys: &'a KeyRing) -> Self {
+        PrivateJar { jar, keys }
+    }
+
+    /// Decrypted value of the cookie `name`, trying every key
+    pub fn get(&self, name: &str) -> Option<String> {
+        let value = self.jar.get(name)?;
+        decrypt(self.keys, name, value)
+    }
+
+    /// Adds `cookie` encrypted with the current key
+    pub fn add(&mut self, cookie: SetCookie) {
+        let value = encrypt(self.keys.current(), cookie.name(), cookie.value());
+        self.jar.add(cookie.with_value(value));
+    }
+
+    pub fn remove(&mut self, cookie: SetCookie) {
+        self.jar.remove(cookie);
+    }
+}
+
+fn cipher(key: &Key) -> Aes256Gcm {
+    Aes256Gcm::new_from_slice(key.encryption()).expect("keys are 32 bytes")
+}
+
+fn encrypt(key: &Key, name: &str, value: &str) -> String {
+    let mut nonce = [0; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let payload = Payload {
+        msg: value.as_bytes(),
+        aad: name.as_bytes(),
+    };
+    let ciphertext = cipher(key)
+        .encrypt(Nonce::from_slice(&nonce), payload)
+        .expect("cookie values are far below the AES-GCM limit");
+
+    let mut data = nonce.to_vec();
+    data.extend_from_slice(&ciphertext);
+    URL_SAFE_NO_PAD.encode(data)
+}
+
+fn decrypt(keys: &KeyRing, name: &str, encrypted: &str) -> Option<String> {
+    let data = URL_SAFE_NO_PAD.decode(encrypted).ok()?;
+    if data.len() <= NONCE_LEN {
+        return None;
+    }
+
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    let value = keys.keys().find_map(|key| {
+        let payload = Payload {
+            msg: ciphertext,
+            aad: name.as_bytes(),
+        };
+        cipher(key).decrypt(Nonce::from_slice(nonce), payload).ok()
+    })?;
+
+    String::from_utf8(value).ok()
+}
+
+#[cfg(test)]
+mod tests_private {
+    use super::*;
+
+    fn cookie(name: &str, value: &str) -> SetCookie {
+        SetCookie::builder(name, value).build().unwrap()
+    }
+
+    #[test]
+    fn test_encrypt_and_decrypt() {
+        let keys = KeyRing::new(Key::derive_from(&[1; 32]));
+        let mut jar = CookieJar::new();
+        jar.private(&keys).add(cookie("session", "user=42"));
+
+        let encrypted = jar.get("session").unwrap().to_string();
+        assert!(!encrypted.contains("42"));
+        assert!(SetCookie::builder("session", &encrypted).build().is_ok());
+        assert_eq!(
+            jar.private(&keys).get("session"),
+            Some("user=42".to_string())
+        );
+
+        // a random nonce makes every encryption different
+        let again = encrypt(keys.current(), "session", "user=42");
+        assert_ne!(again, encrypted);
+        assert_eq!(
+            decrypt(&keys, "session", &again),
+            Some("user=42".to_string())
+        );
+    }
+
+    #[test]
+    fn test_tampering() {
+        let keys = KeyRing::new(Key::derive_from(&[1; 32]));
+        let encrypted = encrypt(keys.current(), "session", "user=42");
+
+        let mut data = URL_SAFE_NO_PAD.decode(&encrypted).unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        let tampered = URL_SAFE_NO_PAD.encode(data);
+
+        assert_eq!(decrypt(&keys, "session", &tampered), None);
+        assert_eq!(decrypt(&keys, "other", &encrypted), None);
+        assert_eq!(decrypt(&keys, "session", "user=42"), None);
+        assert_eq!(decrypt(&keys, "session", ""), None);
+
+        let other_keys = KeyRing::new(Key::derive_from(&[2; 32]));
+        assert_eq!(decrypt(&other_keys, "session", &encrypted), None);
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let old = Key::derive_from(&[1; 32]);
+        let new = Key::derive_from(&[2; 32]);
+        let encrypted = encrypt(&old, "session", "user=42");
+
+        let keys = KeyRing::new(new).with_previous(old);
+        assert_eq!(
+            decrypt(&keys, "session", &encrypted),
+            Some("user=42".to_string())
+        );
+    }
+}
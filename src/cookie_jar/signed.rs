@@ -0,0 +1,137 @@
+use crate::cookie_jar::{CookieJar, Key, KeyRing};
+use crate::headers::SetCookie;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Length of a base64 encoded HMAC-SHA256 tag
+const TAG_LEN: usize = 43;
+
+/// Cookies whose value is prefixed with an HMAC-SHA256 tag of their name
+/// and value. Cookies with a missing or wrong tag are treated as absent.
+pub struct SignedJar<'a> {
+    jar: &'a mut CookieJar,
+    keys: &'a KeyRing,
+}
+
+impl<'a> SignedJar<'a> {
+    pub(crate) fn new(jar: &'a mut CookieJar, keys: &'a KeyRing) -> Self {
+        SignedJar { jar, keys }
+    }
+
+    /// Value of the cookie `name` if it was signed with any of the keys
+    pub fn get(&self, name: &str) -> Option<String> {
+        let value = self.jar.get(name)?;
+        verify(self.keys, name, value)
+    }
+
+    /// Adds `cookie` signed with the current key
+    pub fn add(&mut self, cookie: SetCookie) {
+        let value = sign(self.keys.current(), cookie.name(), cookie.value());
+        self.jar.add(cookie.with_value(value));
+    }
+
+    pub fn remove(&mut self, cookie: SetCookie) {
+        self.jar.remove(cookie);
+    }
+}
+
+fn mac(key: &Key, name: &str, value: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.signing())
+        .expect("HMAC accepts keys of any length");
+
+    // the name is signed too, so values cannot be moved to another cookie
+    mac.update(name.as_bytes());
+    mac.update(b"=");
+    mac.update(value.as_bytes());
+    mac
+}
+
+fn sign(key: &Key, name: &str, value: &str) -> String {
+    let tag = mac(key, name, value).finalize().into_bytes();
+    format!("{}{}", URL_SAFE_NO_PAD.encode(tag), value)
+}
+
+fn verify(keys: &KeyRing, name: &str, signed: &str) -> Option<String> {
+    if !signed.is_char_boundary(TAG_LEN) {
+        return None;
+    }
+
+    let (tag, value) = signed.split_at(TAG_LEN);
+    let tag = URL_SAFE_NO_PAD.decode(tag).ok()?;
+
+    keys.keys()
+        .any(|key| mac(key, name, value).verify_slice(&tag).is_ok())
+        .then(|| value.to_string())
+}
+
+#[cfg(test)]
+mod tests_signed {
+    use super::*;
+    use crate::headers::Headers;
+    use std::convert::TryFrom;
+
+    fn cookie(name: &str, value: &str) -> SetCookie {
+        SetCookie::builder(name, value).build().unwrap()
+    }
+
+    /// Jar holding the cookies `jar` would make the user agent send
+    fn round_trip(jar: &CookieJar) -> CookieJar {
+        let pairs: Vec<String> =
+            jar.iter().map(|(n, v)| format!("{}={}", n, v)).collect();
+        let headers = format!("Cookie: {}", pairs.join("; "));
+        CookieJar::from_headers(&Headers::try_from(headers).unwrap())
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let keys = KeyRing::new(Key::derive_from(&[1; 32]));
+        let mut jar = CookieJar::new();
+        jar.signed(&keys).add(cookie("user", "42"));
+
+        let signed = jar.get("user").unwrap().to_string();
+        assert_eq!(signed.len(), TAG_LEN + 2);
+        assert!(signed.ends_with("42"));
+        assert_eq!(jar.signed(&keys).get("user"), Some("42".to_string()));
+
+        let mut jar = round_trip(&jar);
+        assert_eq!(jar.signed(&keys).get("user"), Some("42".to_string()));
+        assert_eq!(jar.signed(&keys).get("missing"), None);
+    }
+
+    #[test]
+    fn test_tampering() {
+        let keys = KeyRing::new(Key::derive_from(&[1; 32]));
+        let signed = sign(keys.current(), "user", "42");
+
+        let tampered = format!("{}43", &signed[..TAG_LEN]);
+        assert_eq!(verify(&keys, "user", &tampered), None);
+        assert_eq!(verify(&keys, "admin", &signed), None);
+        assert_eq!(verify(&keys, "user", "42"), None);
+        assert_eq!(verify(&keys, "user", ""), None);
+
+        let other_keys = KeyRing::new(Key::derive_from(&[2; 32]));
+        assert_eq!(verify(&other_keys, "user", &signed), None);
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let old = Key::derive_from(&[1; 32]);
+        let new = Key::derive_from(&[2; 32]);
+        let signed_with_old = sign(&old, "user", "42");
+
+        let keys = KeyRing::new(new.clone()).with_previous(old);
+        assert_eq!(
+            verify(&keys, "user", &signed_with_old),
+            Some("42".to_string())
+        );
+
+        // new cookies use the current key only
+        let mut jar = CookieJar::new();
+        jar.signed(&keys).add(cookie("user", "42"));
+        let keys = KeyRing::new(new);
+        assert_eq!(jar.signed(&keys).get("user"), Some("42".to_string()));
+        assert_eq!(verify(&keys, "user", &signed_with_old), None);
+    }
+}
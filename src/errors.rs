@@ -1,3 +1,4 @@
+use crate::status::StatusCode;
 use std::convert::From;
 use std::{fmt, io, str};
 
@@ -70,6 +71,18 @@ macro_rules! errors {
     };
 }
 
+impl Error {
+    /// Status code of the response to a request which failed with this
+    /// error. Errors of the server itself are `500 Internal Server Error`,
+    /// all others are caused by a malformed request.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Error::RequestNotParsed => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
 errors! {
     [
         (NewLine, "Invalid byte in new line");
@@ -78,6 +91,8 @@ errors! {
         (RequestNotParsed, "Trying to get request before it is not parsed completely");
         (InvalidContentLengthValue, "Content length field contains non digit characters");
         (NoChunkedCoding, "There was transfer-encoding but the last encoding was not chunked");
+        (ConflictingContentLength, "Content-Length field lines have different values");
+        (ContentLengthWithTransferEncoding, "Both Transfer-Encoding and Content-Length are present");
     ],
     [
         (InvalidHttpVersion, "Invalid http version");
@@ -87,9 +102,35 @@ errors! {
         (InvalidHeaderField, "Invalid header field");
         (InvalidHeaderFieldValue, "Header field-value contains invalid token character");
         (ParseIntError, "Parse Int Error");
+        (InvalidProxyHeader, "Invalid PROXY protocol header");
+        (InvalidPercentEncoding, "Invalid percent-encoding");
+        (UnsafePath, "Path cannot be safely mapped to the file system");
+        (InvalidQuery, "Invalid query string");
+        (InvalidCookie, "Invalid cookie");
+        (InvalidEntityTag, "Invalid entity-tag");
+        (InvalidHttpDate, "Invalid HTTP-date");
+        (InvalidRange, "Invalid range");
     ],
     [
         (InvalidUtf8String, Vec<u8>, "Invalid utf-8 encoding");
         (InvalidTokenChar, Vec<u8>, "Invalid token character");
     ]
 }
+
+#[cfg(test)]
+mod tests_errors {
+    use super::*;
+
+    #[test]
+    fn test_status_code() {
+        assert_eq!(
+            Error::RequestNotParsed.status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        assert_eq!(Error::InvalidUri.status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            Error::InvalidQuery("a".into()).status_code(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+}
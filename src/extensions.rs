@@ -0,0 +1,126 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+
+/// A map holding at most one value of each type, used by middleware to hand
+/// data like the authenticated user or a request id to later handlers.
+///
+/// Values have to be `Send + Sync` so that requests and responses can move
+/// between threads.
+#[derive(Default)]
+pub struct Extensions {
+    map: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl Extensions {
+    pub fn new() -> Extensions {
+        Extensions {
+            map: HashMap::new(),
+        }
+    }
+
+    /// Inserts `value`, returning the previous value of the same type
+    pub fn insert<T: Send + Sync + 'static>(&mut self, value: T) -> Option<T> {
+        self.map
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|previous| previous.downcast::<T>().ok())
+            .map(|previous| *previous)
+    }
+
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.map
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref::<T>())
+    }
+
+    pub fn get_mut<T: Send + Sync + 'static>(&mut self) -> Option<&mut T> {
+        self.map
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_mut::<T>())
+    }
+
+    pub fn remove<T: Send + Sync + 'static>(&mut self) -> Option<T> {
+        self.map
+            .remove(&TypeId::of::<T>())
+            .and_then(|value| value.downcast::<T>().ok())
+            .map(|value| *value)
+    }
+
+    pub fn contains<T: Send + Sync + 'static>(&self) -> bool {
+        self.map.contains_key(&TypeId::of::<T>())
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Extensions")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests_extensions {
+    use super::Extensions;
+
+    #[derive(Debug, PartialEq)]
+    struct UserId(u64);
+
+    #[derive(Debug, PartialEq)]
+    struct RequestId(String);
+
+    #[test]
+    fn test_insert_get() {
+        let mut extensions = Extensions::new();
+        assert!(extensions.is_empty());
+
+        assert_eq!(extensions.insert(UserId(7)), None);
+        assert_eq!(extensions.insert(RequestId("abc".into())), None);
+        assert_eq!(extensions.len(), 2);
+
+        assert_eq!(extensions.get::<UserId>(), Some(&UserId(7)));
+        assert_eq!(
+            extensions.get::<RequestId>(),
+            Some(&RequestId("abc".into()))
+        );
+        assert_eq!(extensions.get::<u64>(), None);
+    }
+
+    #[test]
+    fn test_replace_and_mutate() {
+        let mut extensions = Extensions::new();
+        extensions.insert(UserId(1));
+        assert_eq!(extensions.insert(UserId(2)), Some(UserId(1)));
+
+        extensions.get_mut::<UserId>().unwrap().0 += 1;
+        assert_eq!(extensions.get::<UserId>(), Some(&UserId(3)));
+        assert_eq!(extensions.len(), 1);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut extensions = Extensions::new();
+        extensions.insert(UserId(1));
+        extensions.insert(5u8);
+
+        assert!(extensions.contains::<UserId>());
+        assert_eq!(extensions.remove::<UserId>(), Some(UserId(1)));
+        assert!(!extensions.contains::<UserId>());
+        assert_eq!(extensions.remove::<UserId>(), None);
+
+        extensions.clear();
+        assert!(extensions.is_empty());
+    }
+}
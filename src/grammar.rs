@@ -1,4 +1,23 @@
 use regex::Regex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DAY_NAMES: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// day-name-l of the obsolete RFC 850 date format
+const LONG_DAY_NAMES: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+const MONTH_NAMES: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct",
+    "Nov", "Dec",
+];
 
 fn byte_to_bool(bytes: [u8; 256]) -> [bool; 256] {
     let mut result = [false; 256];
@@ -83,6 +102,35 @@ lazy_static! {
         result
     };
 
+    /// unreserved = ALPHA / DIGIT / "-" / "." / "_" / "~"
+    static ref UNRESERVED: [bool; 256] = {
+        let mut result = [false; 256];
+        let others = "-._~";
+
+        for (i, r) in result.iter_mut().enumerate() {
+            *r = (i as u8).is_ascii_alphanumeric();
+        }
+
+        for s in others.chars() {
+            result[s as usize] = true;
+        }
+
+        result
+    };
+
+    /// sub-delims = "!" / "$" / "&" / "'" / "(" / ")"
+    ///             / "*" / "+" / "," / ";" / "="
+    static ref SUB_DELIMS: [bool; 256] = {
+        let mut result = [false; 256];
+        let sub_delims = "!$&'()*+,;=";
+
+        for s in sub_delims.chars() {
+            result[s as usize] = true;
+        }
+
+        result
+    };
+
     /// 0-9, A-F
     static ref HEX_DIGITS: [bool; 256] = {
         let mut result = [false; 256];
@@ -196,9 +244,261 @@ pub fn is_hex_digit(byte: u8) -> bool {
     HEX_DIGITS[byte as usize]
 }
 
+#[inline]
+pub fn is_unreserved(byte: u8) -> bool {
+    UNRESERVED[byte as usize]
+}
+
+#[inline]
+pub fn is_sub_delim(byte: u8) -> bool {
+    SUB_DELIMS[byte as usize]
+}
+
+/// Checks that `bytes` only contains unreserved characters, sub-delims,
+/// the given `extra` characters and well formed pct-encoded octets
+pub fn is_uri_component(bytes: &[u8], extra: &[u8]) -> bool {
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b == b'%' {
+            let is_pct_encoded = i + 2 < bytes.len()
+                && is_hex_digit(bytes[i + 1])
+                && is_hex_digit(bytes[i + 2]);
+            if !is_pct_encoded {
+                return false;
+            }
+
+            i += 3;
+            continue;
+        }
+
+        if !(is_unreserved(b) || is_sub_delim(b) || extra.contains(&b)) {
+            return false;
+        }
+
+        i += 1;
+    }
+
+    true
+}
+
+/// Formats `time` as an IMF-fixdate, RFC 7231 section 7.1.1.1, like
+/// `Sun, 06 Nov 1994 08:49:37 GMT`. Fractions of a second are dropped.
+pub fn format_http_date(time: SystemTime) -> String {
+    let seconds = match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs_f64().ceil() as i64),
+    };
+
+    let days = seconds.div_euclid(86400);
+    let time_of_day = seconds.rem_euclid(86400);
+    let (year, month, day) = civil_from_days(days);
+
+    // 1970-01-01 was a Thursday
+    let day_name = DAY_NAMES[(days + 3).rem_euclid(7) as usize];
+
+    format!(
+        "{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT",
+        day_name,
+        day,
+        MONTH_NAMES[month as usize - 1],
+        year,
+        time_of_day / 3600,
+        time_of_day % 3600 / 60,
+        time_of_day % 60
+    )
+}
+
+/// Parses an HTTP-date, RFC 7231 section 7.1.1.1. Besides the IMF-fixdate
+/// `Sun, 06 Nov 1994 08:49:37 GMT` recipients have to accept the obsolete
+/// `Sunday, 06-Nov-94 08:49:37 GMT` and `Sun Nov  6 08:49:37 1994` forms.
+pub fn parse_http_date(value: &str) -> Option<SystemTime> {
+    parse_http_date_in(value, current_year())
+}
+
+/// Like [`parse_http_date`], resolving the two digit years of the RFC 850
+/// form relative to `current_year`
+pub(crate) fn parse_http_date_in(
+    value: &str,
+    current_year: u32,
+) -> Option<SystemTime> {
+    parse_imf_fixdate(value)
+        .or_else(|| parse_rfc850_date(value, current_year))
+        .or_else(|| parse_asctime_date(value))
+}
+
+fn current_year() -> u32 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    let (year, _, _) = civil_from_days(now.div_euclid(86400));
+    year as u32
+}
+
+/// IMF-fixdate = day-name "," SP date1 SP time-of-day SP GMT
+fn parse_imf_fixdate(value: &str) -> Option<SystemTime> {
+    let parts: Vec<&str> = value.split(' ').collect();
+    let (day_name, day, month, year, time, zone) = match parts[..] {
+        [a, b, c, d, e, f] => (a, b, c, d, e, f),
+        _ => return None,
+    };
+
+    let day_name = day_name.strip_suffix(',')?;
+    if !DAY_NAMES.contains(&day_name) || zone != "GMT" {
+        return None;
+    }
+
+    let day = parse_digits(day, 2)?;
+    let year = parse_digits(year, 4)?;
+    to_system_time(year, month, day, time)
+}
+
+/// rfc850-date = day-name-l "," SP date2 SP time-of-day SP GMT
+/// date2 = day "-" month "-" 2DIGIT
+fn parse_rfc850_date(value: &str, current_year: u32) -> Option<SystemTime> {
+    let parts: Vec<&str> = value.split(' ').collect();
+    let (day_name, date, time, zone) = match parts[..] {
+        [a, b, c, d] => (a, b, c, d),
+        _ => return None,
+    };
+
+    let day_name = day_name.strip_suffix(',')?;
+    if !LONG_DAY_NAMES.contains(&day_name) || zone != "GMT" {
+        return None;
+    }
+
+    let mut date = date.split('-');
+    let day = parse_digits(date.next()?, 2)?;
+    let month = date.next()?;
+    let year = parse_digits(date.next()?, 2)?;
+    if date.next().is_some() {
+        return None;
+    }
+
+    to_system_time(full_year(year, current_year), month, day, time)
+}
+
+/// The latest year ending in the two digits `year` which is at most 50
+/// years after `current_year`, RFC 7231 section 7.1.1.1
+fn full_year(year: u32, current_year: u32) -> u32 {
+    let year = current_year - current_year % 100 + year;
+    if year > current_year + 50 {
+        year - 100
+    } else if year + 100 <= current_year + 50 {
+        year + 100
+    } else {
+        year
+    }
+}
+
+/// asctime-date = day-name SP month SP ( 2DIGIT / ( SP DIGIT ) ) SP
+///                time-of-day SP year
+fn parse_asctime_date(value: &str) -> Option<SystemTime> {
+    let bytes = value.as_bytes();
+    if bytes.len() != 24 || !value.is_ascii() {
+        return None;
+    }
+
+    if [3, 7, 10, 19].iter().any(|i| bytes[*i] != b' ') {
+        return None;
+    }
+
+    if !DAY_NAMES.contains(&&value[0..3]) {
+        return None;
+    }
+
+    let day = match value[8..10].strip_prefix(' ') {
+        Some(digit) => parse_digits(digit, 1)?,
+        None => parse_digits(&value[8..10], 2)?,
+    };
+    let year = parse_digits(&value[20..24], 4)?;
+    to_system_time(year, &value[4..7], day, &value[11..19])
+}
+
+fn to_system_time(
+    year: u32,
+    month: &str,
+    day: u32,
+    time: &str,
+) -> Option<SystemTime> {
+    let month = MONTH_NAMES.iter().position(|m| *m == month)? as u32 + 1;
+    if day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+
+    let mut time = time.split(':');
+    let hour = parse_digits(time.next()?, 2)?;
+    let minute = parse_digits(time.next()?, 2)?;
+    let second = parse_digits(time.next()?, 2)?;
+    if time.next().is_some() || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let days = days_from_civil(year as i64, month, day);
+    let seconds = days * 86400 + (hour * 3600 + minute * 60 + second) as i64;
+
+    if seconds >= 0 {
+        UNIX_EPOCH.checked_add(Duration::from_secs(seconds as u64))
+    } else {
+        UNIX_EPOCH.checked_sub(Duration::from_secs(-seconds as u64))
+    }
+}
+
+fn parse_digits(value: &str, len: usize) -> Option<u32> {
+    if value.len() != len || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    value.parse().ok()
+}
+
+// `u32::is_multiple_of` needs Rust 1.87
+#[allow(clippy::manual_is_multiple_of)]
+fn days_in_month(year: u32, month: u32) -> u32 {
+    let is_leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    match month {
+        2 if is_leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Year, month and day of the `days`th day after 1970-01-01, from
+/// http://howardhinnant.github.io/date_algorithms.html
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+/// Inverse of `civil_from_days`
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 } as i64;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146097 + doe - 719468
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{HEX_DIGITS, TOKEN_CHAR};
+    use super::{
+        format_http_date, full_year, is_uri_component, parse_http_date,
+        parse_http_date_in, HEX_DIGITS, TOKEN_CHAR,
+    };
+    use std::time::{Duration, UNIX_EPOCH};
 
     #[test]
     fn test_token_char() {
@@ -221,4 +521,100 @@ mod tests {
             assert_eq!(digits.contains(&i), HEX_DIGITS[i as usize]);
         }
     }
+
+    #[test]
+    fn test_uri_component() {
+        assert!(is_uri_component(b"abc-._~!$&'()*+,;=", b""));
+        assert!(is_uri_component(b"a%2Fb%c3%A9", b""));
+        assert!(is_uri_component(b"a:b@c", b":@"));
+        assert!(!is_uri_component(b"a:b", b""));
+        assert!(!is_uri_component(b"a b", b""));
+        assert!(!is_uri_component(b"a%2", b""));
+        assert!(!is_uri_component(b"a%zz", b""));
+        assert!(!is_uri_component(b"#", b"/?"));
+    }
+
+    #[test]
+    fn test_format_http_date() {
+        let time = UNIX_EPOCH + Duration::from_secs(784111777);
+        assert_eq!(format_http_date(time), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(
+            format_http_date(UNIX_EPOCH),
+            "Thu, 01 Jan 1970 00:00:00 GMT"
+        );
+
+        let time = UNIX_EPOCH + Duration::from_millis(951782400_999);
+        assert_eq!(format_http_date(time), "Tue, 29 Feb 2000 00:00:00 GMT");
+
+        let time = UNIX_EPOCH - Duration::from_secs(1);
+        assert_eq!(format_http_date(time), "Wed, 31 Dec 1969 23:59:59 GMT");
+    }
+
+    #[test]
+    fn test_parse_http_date() {
+        let time = parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(time, Some(UNIX_EPOCH + Duration::from_secs(784111777)));
+
+        let time = parse_http_date("Tue, 29 Feb 2000 23:59:59 GMT").unwrap();
+        assert_eq!(format_http_date(time), "Tue, 29 Feb 2000 23:59:59 GMT");
+
+        for value in [
+            "",
+            "Sun, 06 Nov 1994 08:49:37 UTC",
+            "Sun, 6 Nov 1994 08:49:37 GMT",
+            "Sun 06 Nov 1994 08:49:37 GMT",
+            "Sun, 06 Nov 94 08:49:37 GMT",
+            "Sun, 06 Foo 1994 08:49:37 GMT",
+            "Sun, 06 Nov 1994 24:00:00 GMT",
+            "Sun, 06 Nov 1994 08:49 GMT",
+            "Mon, 29 Feb 1900 00:00:00 GMT",
+            "Sun,  06 Nov 1994 08:49:37 GMT",
+            "Sun, 06-Nov-94 08:49:37 GMT",
+            "Sun Nov 6 08:49:37 1994",
+            "Sun Nov 06 08:49:37 94",
+        ]
+        .iter()
+        {
+            assert_eq!(parse_http_date(value), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_parse_obsolete_http_dates() {
+        let expected = Some(UNIX_EPOCH + Duration::from_secs(784111777));
+        assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), expected);
+        assert_eq!(parse_http_date("Sun Nov  6 08:49:37 1994"), expected);
+
+        let time = parse_http_date("Mon Feb 29 12:00:00 2016").unwrap();
+        assert_eq!(format_http_date(time), "Mon, 29 Feb 2016 12:00:00 GMT");
+
+        // two digit years more than 50 years ahead are in the past
+        let value = "Friday, 01-Jan-99 00:00:00 GMT";
+        let time = parse_http_date_in(value, 2026).unwrap();
+        assert_eq!(format_http_date(time), "Fri, 01 Jan 1999 00:00:00 GMT");
+        let time = parse_http_date_in(value, 2060).unwrap();
+        assert_eq!(format_http_date(time), "Thu, 01 Jan 2099 00:00:00 GMT");
+
+        assert_eq!(full_year(76, 2026), 2076);
+        assert_eq!(full_year(77, 2026), 1977);
+        assert_eq!(full_year(0, 2099), 2100);
+
+        for value in [
+            "Sun, 06-Nov-94 08:49:37 GMT",
+            "Sunday, 06-Nov-1994 08:49:37 GMT",
+            "Sunday, 6-Nov-94 08:49:37 GMT",
+            "Sunday, 06-Nov-94 08:49:37 UTC",
+            "Sunday 06-Nov-94 08:49:37 GMT",
+            "Sun Nov 06 08:49:37 1994 ",
+            "Sun Nov 6  08:49:37 1994",
+            "Sun Nov  6 08:49:37 94  ",
+            "Sunday Nov  6 08:49:37 1994",
+            "Sun Foo  6 08:49:37 1994",
+            "Sun Feb 30 08:49:37 1994",
+        ]
+        .iter()
+        {
+            assert_eq!(parse_http_date(value), None, "{}", value);
+        }
+    }
 }
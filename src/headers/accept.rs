@@ -1,29 +1,380 @@
-use crate::headers::{Header, RequestHeader};
-use std::any::Any;
+use crate::errors::Error as HttpErrors;
+use crate::grammar::is_token;
+use crate::headers::parameters::{
+    format_quality, parse_parameter, parse_quality, quote, split_quoted,
+};
+use crate::headers::{Headers, RequestHeader, TypedHeader, ACCEPT_HEADER_NAME};
+use std::convert::TryFrom;
+use std::fmt;
 
+/// The `Accept` header, RFC 7231 section 5.3.2.
+///
+/// Content negotiation must not fail a request, so media ranges which
+/// cannot be parsed are skipped. A field without any valid range accepts
+/// every media type, like a request without `Accept`.
+#[derive(Clone, Debug, PartialEq)]
 pub struct AcceptHeader {
+    ranges: Vec<MediaRange>,
+}
+
+impl AcceptHeader {
+    /// The media ranges of every `Accept` field line of `headers`, as a
+    /// client may split the list over several lines. `None` if there is
+    /// none.
+    pub fn from_headers(headers: &Headers) -> Option<AcceptHeader> {
+        let fields: Vec<&AcceptHeader> = headers
+            .get_all(ACCEPT_HEADER_NAME)
+            .into_iter()
+            .filter_map(|h| h.as_any().downcast_ref::<AcceptHeader>())
+            .collect();
+
+        if fields.is_empty() {
+            return None;
+        }
+
+        let ranges = fields.iter().flat_map(|f| f.ranges.clone()).collect();
+        Some(AcceptHeader { ranges })
+    }
+
+    /// Media ranges in the order they were received
+    pub fn media_ranges(&self) -> &[MediaRange] {
+        &self.ranges
+    }
+
+    /// Media ranges from the most to the least preferred. Ranges of equal
+    /// weight are ordered by specificity, so `text/html;level=1` comes
+    /// before `text/html`, which comes before `text/*` and `*/*`.
+    pub fn by_preference(&self) -> Vec<&MediaRange> {
+        let mut ranges: Vec<&MediaRange> = self.ranges.iter().collect();
+        ranges.sort_by(|a, b| {
+            let a = (a.quality, a.specificity());
+            let b = (b.quality, b.specificity());
+            b.cmp(&a)
+        });
+
+        ranges
+    }
+
+    /// Weight of `media_type`, taken from the most specific range matching
+    /// it. 0 if there is none, which means it is not acceptable.
+    pub fn quality_of(&self, media_type: &str) -> f32 {
+        match MediaRange::try_from(media_type) {
+            Ok(media_type) => self.quality(&media_type) as f32 / 1000.0,
+            Err(_) => 0.0,
+        }
+    }
+
+    /// The most acceptable of the media types a handler can produce. Types
+    /// with equal weight are picked in the order of `available`, so it
+    /// should list the server's preference first. `None` if none of them
+    /// is acceptable.
+    ///
+    /// For a request, use the header returned by
+    /// [`AcceptHeader::from_headers`] so that every `Accept` line counts.
+    pub fn best_match<'a>(&self, available: &[&'a str]) -> Option<&'a str> {
+        let mut best: Option<(&'a str, u16)> = None;
+
+        for media_type in available.iter() {
+            let quality = match MediaRange::try_from(*media_type) {
+                Ok(m) => self.quality(&m),
+                Err(_) => continue,
+            };
+
+            let is_better = match best {
+                None => quality > 0,
+                Some((_, q)) => quality > q,
+            };
+
+            if is_better {
+                best = Some((media_type, quality));
+            }
+        }
+
+        best.map(|(m, _)| m)
+    }
+
+    fn quality(&self, media_type: &MediaRange) -> u16 {
+        if self.ranges.is_empty() {
+            return 1000;
+        }
+
+        self.ranges
+            .iter()
+            .filter(|r| r.matches(media_type))
+            .max_by_key(|r| r.specificity())
+            .map_or(0, |r| r.quality)
+    }
+}
+
+impl TryFrom<&str> for AcceptHeader {
+    type Error = HttpErrors;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let ranges = split_quoted(value, ',')
+            .into_iter()
+            .filter_map(|r| MediaRange::try_from(r).ok())
+            .collect();
+
+        Ok(AcceptHeader { ranges })
+    }
+}
+
+impl TypedHeader for AcceptHeader {
+    const NAME: &'static str = ACCEPT_HEADER_NAME;
+
+    fn parse(value: &str) -> Result<Self, HttpErrors> {
+        AcceptHeader::try_from(value)
+    }
+
+    fn encode(&self) -> String {
+        let ranges: Vec<String> =
+            self.ranges.iter().map(|r| r.to_string()).collect();
+
+        ranges.join(", ")
+    }
+}
+
+impl RequestHeader for AcceptHeader {}
+
+/// One element of `Accept`, like `text/html;level=1;q=0.7`. Type, subtype
+/// and parameter names are lower-cased.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MediaRange {
     typ: String,
     sub_type: String,
+    params: Vec<(String, String)>,
+    quality: u16,
 }
 
-impl Header for AcceptHeader {
-    fn name(&self) -> &str {
-        "accept"
+impl MediaRange {
+    pub fn typ(&self) -> &str {
+        &self.typ
     }
 
-    fn value(&self) -> String {
-        // TODO
-        unimplemented!();
+    pub fn sub_type(&self) -> &str {
+        &self.sub_type
     }
 
-    fn header_string(&self) -> String {
-        let s = format!("{}: {}", self.name(), self.value());
-        return s;
+    /// Media type parameters, without the weight and the accept-ext
+    /// parameters following it
+    pub fn params(&self) -> &[(String, String)] {
+        &self.params
     }
 
-    fn as_any(&self) -> &dyn Any {
-        self
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Weight between 0 and 1, 1 if it was not given
+    pub fn quality(&self) -> f32 {
+        self.quality as f32 / 1000.0
+    }
+
+    /// Whether `media_type` belongs to this range. Every parameter of the
+    /// range has to be present in `media_type` with the same value.
+    fn matches(&self, media_type: &MediaRange) -> bool {
+        let type_matches = self.typ == "*" || self.typ == media_type.typ;
+        let sub_type_matches =
+            self.sub_type == "*" || self.sub_type == media_type.sub_type;
+
+        type_matches
+            && sub_type_matches
+            && self.params.iter().all(|(name, value)| {
+                media_type
+                    .param(name)
+                    .is_some_and(|v| v.eq_ignore_ascii_case(value))
+            })
+    }
+
+    fn specificity(&self) -> (u8, usize) {
+        let level = match (self.typ.as_str(), self.sub_type.as_str()) {
+            ("*", _) => 0,
+            (_, "*") => 1,
+            _ => 2,
+        };
+
+        (level, self.params.len())
     }
 }
 
-impl RequestHeader for AcceptHeader {}
+impl TryFrom<&str> for MediaRange {
+    type Error = HttpErrors;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let invalid = || HttpErrors::InvalidHeaderFieldValue(value.to_string());
+
+        let mut parts = split_quoted(value, ';').into_iter();
+        let media_type = parts.next().ok_or_else(invalid)?;
+        let (typ, sub_type) = media_type.split_once('/').ok_or_else(invalid)?;
+
+        let is_valid = |s: &str| !s.is_empty() && is_token(s.as_bytes());
+        if !is_valid(typ) || !is_valid(sub_type) {
+            return Err(invalid());
+        }
+
+        // "*/html" is not a media range
+        if typ == "*" && sub_type != "*" {
+            return Err(invalid());
+        }
+
+        let mut params = vec![];
+        let mut quality = 1000;
+        for parameter in parts {
+            let (name, value) = parse_parameter(parameter)?;
+
+            // everything after the weight is accept-ext
+            if name == "q" {
+                quality = parse_quality(&value)?;
+                break;
+            }
+
+            params.push((name, value));
+        }
+
+        Ok(MediaRange {
+            typ: typ.to_ascii_lowercase(),
+            sub_type: sub_type.to_ascii_lowercase(),
+            params,
+            quality,
+        })
+    }
+}
+
+impl fmt::Display for MediaRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.typ, self.sub_type)?;
+        for (name, value) in self.params.iter() {
+            write!(f, ";{}={}", name, quote(value))?;
+        }
+
+        if self.quality != 1000 {
+            write!(f, ";q={}", format_quality(self.quality))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests_accept {
+    use super::*;
+    use crate::request::RequestBuilder;
+
+    const RFC_EXAMPLE: &str = "text/*;q=0.3, text/html;q=0.7, \
+        text/html;level=1, text/html;level=2;q=0.4, */*;q=0.5";
+
+    #[test]
+    fn test_parse() {
+        let accept =
+            AcceptHeader::try_from("Text/HTML;Level=\"1\";q=0.8;ext=x, */*")
+                .unwrap();
+        let ranges = accept.media_ranges();
+        assert_eq!(ranges.len(), 2);
+        assert_eq!(ranges[0].typ(), "text");
+        assert_eq!(ranges[0].sub_type(), "html");
+        assert_eq!(ranges[0].param("level"), Some("1"));
+        assert_eq!(ranges[0].params().len(), 1);
+        assert_eq!(ranges[0].quality(), 0.8);
+        assert_eq!(ranges[1].quality(), 1.0);
+
+        assert_eq!(accept.encode(), "text/html;level=1;q=0.8, */*");
+    }
+
+    #[test]
+    fn test_invalid() {
+        for value in
+            ["text", "*/html", "text/html;q=2", "text/;q=1", "a b/c"].iter()
+        {
+            assert!(MediaRange::try_from(*value).is_err(), "{}", value);
+            let accept = AcceptHeader::try_from(*value).unwrap();
+            assert!(accept.media_ranges().is_empty(), "{}", value);
+            assert_eq!(accept.best_match(&["text/html"]), Some("text/html"));
+        }
+
+        // the default of old Java clients
+        let accept = AcceptHeader::try_from(
+            "text/html, image/gif, image/jpeg, *; q=.2, */*; q=.2",
+        )
+        .unwrap();
+        assert_eq!(accept.encode(), "text/html, image/gif, image/jpeg");
+    }
+
+    #[test]
+    fn test_request_with_invalid_accept() {
+        let buffer = "GET / HTTP/1.1\r\nAccept: text/html, *; q=.2\r\n\
+                      Accept: application/json;q=0.5\r\n\r\n";
+        let mut builder = RequestBuilder::new();
+        builder
+            .parse(buffer.as_bytes().to_vec(), buffer.len())
+            .unwrap();
+        let request = builder.build();
+
+        // every line counts, not only the first one
+        let accept = AcceptHeader::from_headers(request.headers()).unwrap();
+        assert_eq!(accept.encode(), "text/html, application/json;q=0.5");
+        let available = ["image/png", "application/json"];
+        assert_eq!(accept.best_match(&available), Some("application/json"));
+
+        assert!(AcceptHeader::from_headers(&Headers::new()).is_none());
+    }
+
+    #[test]
+    fn test_by_preference() {
+        let accept = AcceptHeader::try_from(RFC_EXAMPLE).unwrap();
+        let ranges: Vec<String> = accept
+            .by_preference()
+            .iter()
+            .map(|r| r.to_string())
+            .collect();
+
+        assert_eq!(
+            ranges,
+            vec![
+                "text/html;level=1",
+                "text/html;q=0.7",
+                "*/*;q=0.5",
+                "text/html;level=2;q=0.4",
+                "text/*;q=0.3",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quality_of() {
+        // the example of RFC 7231 section 5.3.2
+        let accept = AcceptHeader::try_from(RFC_EXAMPLE).unwrap();
+        assert_eq!(accept.quality_of("text/html;level=1"), 1.0);
+        assert_eq!(accept.quality_of("text/html"), 0.7);
+        assert_eq!(accept.quality_of("text/plain"), 0.3);
+        assert_eq!(accept.quality_of("image/jpeg"), 0.5);
+        assert_eq!(accept.quality_of("text/html;level=2"), 0.4);
+        assert_eq!(accept.quality_of("text/html;level=3"), 0.7);
+    }
+
+    #[test]
+    fn test_best_match() {
+        let accept = AcceptHeader::try_from(
+            "application/json, text/html;q=0.9, */*;q=0.1",
+        )
+        .unwrap();
+        let available = ["text/html", "application/json"];
+        assert_eq!(accept.best_match(&available), Some("application/json"));
+        assert_eq!(accept.best_match(&["image/png"]), Some("image/png"));
+        assert_eq!(accept.best_match(&[]), None);
+
+        // equal weights keep the server's order
+        let accept = AcceptHeader::try_from("text/*").unwrap();
+        let available = ["application/json", "text/plain", "text/html"];
+        assert_eq!(accept.best_match(&available), Some("text/plain"));
+
+        // q=0 excludes a type even if a wildcard would match it
+        let accept =
+            AcceptHeader::try_from("text/plain;q=0, text/*;q=0.5").unwrap();
+        assert_eq!(accept.best_match(&["text/plain"]), None);
+        let available = ["text/plain", "text/csv"];
+        assert_eq!(accept.best_match(&available), Some("text/csv"));
+    }
+}
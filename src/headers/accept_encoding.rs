@@ -0,0 +1,182 @@
+use crate::errors::Error as HttpErrors;
+use crate::grammar::is_token;
+use crate::headers::parameters::{format_weighted, parse_weighted_list};
+use crate::headers::{RequestHeader, TypedHeader, ACCEPT_ENCODING_HEADER_NAME};
+use std::convert::TryFrom;
+
+const IDENTITY: &str = "identity";
+
+/// The `Accept-Encoding` header, RFC 7231 section 5.3.4.
+///
+/// Invalid codings are left out. A field without any valid coding only
+/// accepts `identity`, like an empty one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AcceptEncoding {
+    codings: Vec<(String, u16)>,
+}
+
+impl AcceptEncoding {
+    /// Content codings with their weights, in the order they were received.
+    /// Codings are lower-cased, "*" stands for any other coding.
+    pub fn codings(&self) -> impl Iterator<Item = (&str, f32)> {
+        self.codings
+            .iter()
+            .map(|(c, q)| (c.as_str(), *q as f32 / 1000.0))
+    }
+
+    /// Weight of `coding`, 0 if it is not acceptable.
+    ///
+    /// A coding which is not listed gets the weight of "*". If there is no
+    /// "*" either, only `identity` is acceptable.
+    pub fn quality_of(&self, coding: &str) -> f32 {
+        self.quality(coding) as f32 / 1000.0
+    }
+
+    /// The most acceptable of the codings a handler can produce, with ties
+    /// going to the first one in `available`. List `identity` to fall back
+    /// to an unencoded response. `None` if nothing in `available` is
+    /// acceptable, in which case the server may answer 406 or ignore the
+    /// header.
+    pub fn best_match<'a>(&self, available: &[&'a str]) -> Option<&'a str> {
+        let mut best: Option<(&'a str, u16)> = None;
+
+        for coding in available.iter() {
+            let quality = self.quality(coding);
+            let is_better = match best {
+                None => quality > 0,
+                Some((_, q)) => quality > q,
+            };
+
+            if is_better {
+                best = Some((coding, quality));
+            }
+        }
+
+        best.map(|(c, _)| c)
+    }
+
+    fn quality(&self, coding: &str) -> u16 {
+        let find = |name: &str| {
+            self.codings
+                .iter()
+                .find(|(c, _)| c.eq_ignore_ascii_case(name))
+                .map(|(_, q)| *q)
+        };
+
+        let default = if coding.eq_ignore_ascii_case(IDENTITY) {
+            1000
+        } else {
+            0
+        };
+
+        find(coding).or_else(|| find("*")).unwrap_or(default)
+    }
+}
+
+impl TryFrom<&str> for AcceptEncoding {
+    type Error = HttpErrors;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let is_coding = |c: &str| !c.is_empty() && is_token(c.as_bytes());
+        let codings = parse_weighted_list(value, is_coding);
+        Ok(AcceptEncoding { codings })
+    }
+}
+
+impl TypedHeader for AcceptEncoding {
+    const NAME: &'static str = ACCEPT_ENCODING_HEADER_NAME;
+
+    fn parse(value: &str) -> Result<Self, HttpErrors> {
+        AcceptEncoding::try_from(value)
+    }
+
+    fn encode(&self) -> String {
+        let codings: Vec<String> = self
+            .codings
+            .iter()
+            .map(|(c, q)| format_weighted(c, *q))
+            .collect();
+
+        codings.join(", ")
+    }
+}
+
+impl RequestHeader for AcceptEncoding {}
+
+#[cfg(test)]
+mod tests_accept_encoding {
+    use super::*;
+    use crate::request::RequestBuilder;
+
+    #[test]
+    fn test_parse() {
+        let accept = AcceptEncoding::try_from("GZIP;q=0.8, br").unwrap();
+        let codings: Vec<(&str, f32)> = accept.codings().collect();
+        assert_eq!(codings, vec![("gzip", 0.8), ("br", 1.0)]);
+        assert_eq!(accept.encode(), "gzip;q=0.8, br");
+
+        // invalid codings are dropped
+        let accept =
+            AcceptEncoding::try_from("gzip;q=2, br;q=abc, g zip, deflate")
+                .unwrap();
+        assert_eq!(accept.encode(), "deflate");
+        let accept = AcceptEncoding::try_from("gzip;q=2").unwrap();
+        assert_eq!(accept.codings().count(), 0);
+    }
+
+    #[test]
+    fn test_request_with_invalid_coding() {
+        let buffer =
+            "GET / HTTP/1.1\r\nAccept-Encoding: gzip;q=abc, br\r\n\r\n";
+        let mut builder = RequestBuilder::new();
+        builder
+            .parse(buffer.as_bytes().to_vec(), buffer.len())
+            .unwrap();
+        let request = builder.build();
+
+        let accept = request.headers().accept_encoding().unwrap();
+        assert_eq!(accept.best_match(&["gzip", "br"]), Some("br"));
+    }
+
+    #[test]
+    fn test_quality_of() {
+        let accept = AcceptEncoding::try_from("gzip;q=0.5, br").unwrap();
+        assert_eq!(accept.quality_of("gzip"), 0.5);
+        assert_eq!(accept.quality_of("Br"), 1.0);
+        assert_eq!(accept.quality_of("deflate"), 0.0);
+        assert_eq!(accept.quality_of("identity"), 1.0);
+
+        let accept = AcceptEncoding::try_from("*;q=0.2, gzip").unwrap();
+        assert_eq!(accept.quality_of("deflate"), 0.2);
+        assert_eq!(accept.quality_of("identity"), 0.2);
+    }
+
+    #[test]
+    fn test_exclusions() {
+        let accept = AcceptEncoding::try_from("gzip, identity;q=0").unwrap();
+        assert_eq!(accept.quality_of("identity"), 0.0);
+        assert_eq!(accept.best_match(&["br", "identity"]), None);
+        assert_eq!(accept.best_match(&["br", "gzip"]), Some("gzip"));
+
+        let accept = AcceptEncoding::try_from("br;q=0.5, *;q=0").unwrap();
+        assert_eq!(accept.quality_of("identity"), 0.0);
+        assert_eq!(accept.best_match(&["gzip", "identity"]), None);
+        assert_eq!(accept.best_match(&["gzip", "br"]), Some("br"));
+    }
+
+    #[test]
+    fn test_best_match() {
+        let accept = AcceptEncoding::try_from("gzip;q=0.8, br").unwrap();
+        let available = ["gzip", "br", "identity"];
+        assert_eq!(accept.best_match(&available), Some("br"));
+        assert_eq!(
+            accept.best_match(&["deflate", "identity"]),
+            Some("identity")
+        );
+        assert_eq!(accept.best_match(&["deflate"]), None);
+
+        // equal weights keep the server's order
+        let accept = AcceptEncoding::try_from("gzip, br").unwrap();
+        assert_eq!(accept.best_match(&["br", "gzip"]), Some("br"));
+    }
+}
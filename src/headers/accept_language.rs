@@ -0,0 +1,238 @@
+use crate::errors::Error as HttpErrors;
+use crate::headers::parameters::{format_weighted, parse_weighted_list};
+use crate::headers::{RequestHeader, TypedHeader, ACCEPT_LANGUAGE_HEADER_NAME};
+use std::cmp::Reverse;
+use std::convert::TryFrom;
+
+/// The `Accept-Language` header, RFC 7231 section 5.3.5, whose language
+/// ranges are matched against language tags as in RFC 4647.
+///
+/// A field without any valid range accepts every language, like a request
+/// without `Accept-Language`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AcceptLanguage {
+    ranges: Vec<(String, u16)>,
+}
+
+impl AcceptLanguage {
+    /// Language ranges with their weights, in the order they were received.
+    /// Ranges are lower-cased.
+    pub fn ranges(&self) -> impl Iterator<Item = (&str, f32)> {
+        self.ranges
+            .iter()
+            .map(|(r, q)| (r.as_str(), *q as f32 / 1000.0))
+    }
+
+    /// Basic filtering, RFC 4647 section 3.3.1. Returns the tags in
+    /// `available` matched by an acceptable range, most preferred first.
+    ///
+    /// A range matches a tag equal to it or starting with it followed by
+    /// "-", so "de" matches "de-CH". A tag takes the weight of the longest
+    /// range matching it, which lets `de;q=1, de-CH;q=0` exclude "de-CH".
+    pub fn filter<'a>(&self, available: &[&'a str]) -> Vec<&'a str> {
+        let mut tags: Vec<(&'a str, u16)> = available
+            .iter()
+            .filter_map(|tag| match self.quality(tag) {
+                0 => None,
+                q => Some((*tag, q)),
+            })
+            .collect();
+
+        tags.sort_by_key(|(_, q)| Reverse(*q));
+        tags.into_iter().map(|(t, _)| t).collect()
+    }
+
+    /// Lookup, RFC 4647 section 3.4. Picks the single best tag in
+    /// `available` by trying the ranges from the most preferred one,
+    /// shortening each range until it equals a tag: "de-ch-1996", then
+    /// "de-ch", then "de". The "*" range is ignored. `None` if nothing
+    /// matches, so the caller can use its default language.
+    pub fn lookup<'a>(&self, available: &[&'a str]) -> Option<&'a str> {
+        let mut ranges: Vec<&(String, u16)> = self
+            .ranges
+            .iter()
+            .filter(|(r, q)| *q > 0 && r != "*")
+            .collect();
+        ranges.sort_by_key(|(_, q)| Reverse(*q));
+
+        for (range, _) in ranges {
+            let mut range = range.as_str();
+            loop {
+                let tag =
+                    available.iter().find(|t| t.eq_ignore_ascii_case(range));
+                if let Some(tag) = tag {
+                    return Some(tag);
+                }
+
+                range = match truncate(range) {
+                    Some(r) => r,
+                    None => break,
+                };
+            }
+        }
+
+        None
+    }
+
+    fn quality(&self, tag: &str) -> u16 {
+        // like a request without Accept-Language
+        if self.ranges.is_empty() {
+            return 1000;
+        }
+
+        self.ranges
+            .iter()
+            .filter(|(range, _)| matches(range, tag))
+            .max_by_key(|(range, _)| match range.as_str() {
+                "*" => 0,
+                r => r.len(),
+            })
+            .map_or(0, |(_, q)| *q)
+    }
+}
+
+/// Whether the basic language range `range` matches `tag`
+fn matches(range: &str, tag: &str) -> bool {
+    if range == "*" {
+        return true;
+    }
+
+    match tag.get(..range.len()) {
+        Some(prefix) if prefix.eq_ignore_ascii_case(range) => {
+            tag.len() == range.len() || tag.as_bytes()[range.len()] == b'-'
+        }
+        _ => false,
+    }
+}
+
+/// `range` without its last subtag, and without a single letter subtag
+/// left at the end like the "x" of "en-x-private"
+fn truncate(range: &str) -> Option<&str> {
+    let mut range = &range[..range.rfind('-')?];
+    if let Some(i) = range.rfind('-') {
+        if range.len() - i == 2 {
+            range = &range[..i];
+        }
+    }
+
+    Some(range)
+}
+
+/// language-range = (1*8ALPHA *("-" 1*8alphanum)) / "*"
+fn is_language_range(range: &str) -> bool {
+    if range == "*" {
+        return true;
+    }
+
+    let mut subtags = range.split('-');
+    let is_primary = |s: &str| {
+        (1..=8).contains(&s.len()) && s.bytes().all(|b| b.is_ascii_alphabetic())
+    };
+    let is_subtag = |s: &str| {
+        (1..=8).contains(&s.len())
+            && s.bytes().all(|b| b.is_ascii_alphanumeric())
+    };
+
+    subtags.next().is_some_and(is_primary) && subtags.all(is_subtag)
+}
+
+impl TryFrom<&str> for AcceptLanguage {
+    type Error = HttpErrors;
+
+    /// Ranges which cannot be parsed, like the common `en_US`, are
+    /// skipped, as content negotiation must not fail a request
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let ranges = parse_weighted_list(value, is_language_range);
+        Ok(AcceptLanguage { ranges })
+    }
+}
+
+impl TypedHeader for AcceptLanguage {
+    const NAME: &'static str = ACCEPT_LANGUAGE_HEADER_NAME;
+
+    fn parse(value: &str) -> Result<Self, HttpErrors> {
+        AcceptLanguage::try_from(value)
+    }
+
+    fn encode(&self) -> String {
+        let ranges: Vec<String> = self
+            .ranges
+            .iter()
+            .map(|(r, q)| format_weighted(r, *q))
+            .collect();
+
+        ranges.join(", ")
+    }
+}
+
+impl RequestHeader for AcceptLanguage {}
+
+#[cfg(test)]
+mod tests_accept_language {
+    use super::*;
+    use crate::request::RequestBuilder;
+
+    #[test]
+    fn test_parse() {
+        let accept =
+            AcceptLanguage::try_from("da, en-GB;q=0.8, en;q=0.7").unwrap();
+        let ranges: Vec<(&str, f32)> = accept.ranges().collect();
+        assert_eq!(ranges, vec![("da", 1.0), ("en-gb", 0.8), ("en", 0.7)]);
+        assert_eq!(accept.encode(), "da, en-gb;q=0.8, en;q=0.7");
+
+        for value in ["en_US", "123", "toolongtag", "en-", "*-US"].iter() {
+            let accept = AcceptLanguage::try_from(*value).unwrap();
+            assert_eq!(accept.ranges().count(), 0, "{}", value);
+            assert_eq!(accept.filter(&["en"]), vec!["en"]);
+        }
+
+        let accept =
+            AcceptLanguage::try_from("en_US, de;q=2, fr;q=0.5").unwrap();
+        assert_eq!(accept.encode(), "fr;q=0.5");
+    }
+
+    #[test]
+    fn test_request_with_invalid_range() {
+        let buffer = "GET / HTTP/1.1\r\nAccept-Language: en_US, de\r\n\r\n";
+        let mut builder = RequestBuilder::new();
+        builder
+            .parse(buffer.as_bytes().to_vec(), buffer.len())
+            .unwrap();
+        let request = builder.build();
+
+        let accept = request.headers().accept_language().unwrap();
+        assert_eq!(accept.lookup(&["en", "de"]), Some("de"));
+    }
+
+    #[test]
+    fn test_filter() {
+        let accept =
+            AcceptLanguage::try_from("de-de, de;q=0.5, fr;q=0.2").unwrap();
+        let available = ["fr-FR", "en", "de", "de-DE-1996", "de-CH", "dea"];
+        assert_eq!(
+            accept.filter(&available),
+            vec!["de-DE-1996", "de", "de-CH", "fr-FR"]
+        );
+
+        let accept = AcceptLanguage::try_from("*, de-CH;q=0").unwrap();
+        assert_eq!(accept.filter(&["de-CH", "de", "en"]), vec!["de", "en"]);
+    }
+
+    #[test]
+    fn test_lookup() {
+        let accept = AcceptLanguage::try_from("fr;q=0.5, de-CH-1996").unwrap();
+        let available = ["en", "de", "fr"];
+        assert_eq!(accept.lookup(&available), Some("de"));
+        assert_eq!(accept.lookup(&["en", "fr-CA", "FR"]), Some("FR"));
+        assert_eq!(accept.lookup(&["en", "fr-CA"]), None);
+
+        let accept = AcceptLanguage::try_from("zh-Hant-CN-x-private1").unwrap();
+        assert_eq!(
+            accept.lookup(&["zh-Hant-CN-x", "zh-Hant"]),
+            Some("zh-Hant")
+        );
+
+        let accept = AcceptLanguage::try_from("*, en;q=0").unwrap();
+        assert_eq!(accept.lookup(&["en", "de"]), None);
+    }
+}
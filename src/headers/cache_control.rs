@@ -0,0 +1,423 @@
+use crate::errors::Error as HttpErrors;
+use crate::grammar::is_token;
+use crate::headers::parameters::{parse_parameter, quote, split_quoted};
+use crate::headers::{GeneralHeader, TypedHeader, CACHE_CONTROL_HEADER_NAME};
+use std::convert::TryFrom;
+use std::fmt;
+use std::time::Duration;
+
+/// delta-seconds larger than this are sent as this, RFC 7234 section 1.2.1
+const MAX_DELTA_SECONDS: u64 = 2147483648;
+
+/// A cache directive, RFC 7234 section 5.2, RFC 8246 and RFC 5861
+#[derive(Clone, Debug, PartialEq)]
+pub enum CacheDirective {
+    MaxAge(Duration),
+    /// `max-stale`, with no limit if `None`
+    MaxStale(Option<Duration>),
+    MinFresh(Duration),
+    /// `no-cache`, limited to the given header fields if any
+    NoCache(Vec<String>),
+    NoStore,
+    NoTransform,
+    OnlyIfCached,
+    MustRevalidate,
+    MustUnderstand,
+    /// `private`, limited to the given header fields if any
+    Private(Vec<String>),
+    ProxyRevalidate,
+    Public,
+    SMaxAge(Duration),
+    Immutable,
+    StaleWhileRevalidate(Duration),
+    StaleIfError(Duration),
+    /// Any other directive, whose name is lower-cased
+    Extension(String, Option<String>),
+}
+
+impl CacheDirective {
+    fn parse(directive: &str) -> Result<CacheDirective, HttpErrors> {
+        let (name, argument) = if directive.contains('=') {
+            let (name, argument) = parse_parameter(directive)?;
+            (name, Some(argument))
+        } else if is_token(directive.as_bytes()) {
+            (directive.to_ascii_lowercase(), None)
+        } else {
+            let directive = directive.to_string();
+            return Err(HttpErrors::InvalidHeaderFieldValue(directive));
+        };
+
+        let seconds = argument.as_deref().and_then(parse_delta_seconds);
+        let directive = match (name.as_str(), &argument, seconds) {
+            ("max-age", _, Some(s)) => CacheDirective::MaxAge(s),
+            ("max-stale", None, _) => CacheDirective::MaxStale(None),
+            ("max-stale", _, Some(s)) => CacheDirective::MaxStale(Some(s)),
+            ("min-fresh", _, Some(s)) => CacheDirective::MinFresh(s),
+            ("s-maxage", _, Some(s)) => CacheDirective::SMaxAge(s),
+            ("stale-while-revalidate", _, Some(s)) => {
+                CacheDirective::StaleWhileRevalidate(s)
+            }
+            ("stale-if-error", _, Some(s)) => CacheDirective::StaleIfError(s),
+            ("no-cache", argument, _) => {
+                CacheDirective::NoCache(parse_field_names(argument))
+            }
+            ("private", argument, _) => {
+                CacheDirective::Private(parse_field_names(argument))
+            }
+            ("no-store", None, _) => CacheDirective::NoStore,
+            ("no-transform", None, _) => CacheDirective::NoTransform,
+            ("only-if-cached", None, _) => CacheDirective::OnlyIfCached,
+            ("must-revalidate", None, _) => CacheDirective::MustRevalidate,
+            ("must-understand", None, _) => CacheDirective::MustUnderstand,
+            ("proxy-revalidate", None, _) => CacheDirective::ProxyRevalidate,
+            ("public", None, _) => CacheDirective::Public,
+            ("immutable", None, _) => CacheDirective::Immutable,
+            // malformed arguments of known directives are kept as they are
+            _ => CacheDirective::Extension(name, argument),
+        };
+
+        Ok(directive)
+    }
+
+    /// Directive name in lower case
+    pub fn name(&self) -> &str {
+        match self {
+            CacheDirective::MaxAge(_) => "max-age",
+            CacheDirective::MaxStale(_) => "max-stale",
+            CacheDirective::MinFresh(_) => "min-fresh",
+            CacheDirective::NoCache(_) => "no-cache",
+            CacheDirective::NoStore => "no-store",
+            CacheDirective::NoTransform => "no-transform",
+            CacheDirective::OnlyIfCached => "only-if-cached",
+            CacheDirective::MustRevalidate => "must-revalidate",
+            CacheDirective::MustUnderstand => "must-understand",
+            CacheDirective::Private(_) => "private",
+            CacheDirective::ProxyRevalidate => "proxy-revalidate",
+            CacheDirective::Public => "public",
+            CacheDirective::SMaxAge(_) => "s-maxage",
+            CacheDirective::Immutable => "immutable",
+            CacheDirective::StaleWhileRevalidate(_) => "stale-while-revalidate",
+            CacheDirective::StaleIfError(_) => "stale-if-error",
+            CacheDirective::Extension(name, _) => name,
+        }
+    }
+}
+
+/// delta-seconds = 1*DIGIT, too large values meaning 2^31
+fn parse_delta_seconds(value: &str) -> Option<Duration> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let seconds = value.parse().unwrap_or(MAX_DELTA_SECONDS);
+    Some(Duration::from_secs(seconds.min(MAX_DELTA_SECONDS)))
+}
+
+/// The quoted `#field-name` argument of `no-cache` and `private`
+fn parse_field_names(argument: &Option<String>) -> Vec<String> {
+    match argument {
+        None => vec![],
+        Some(names) => split_quoted(names, ',')
+            .into_iter()
+            .map(|n| n.to_ascii_lowercase())
+            .collect(),
+    }
+}
+
+impl fmt::Display for CacheDirective {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())?;
+
+        match self {
+            CacheDirective::MaxAge(s)
+            | CacheDirective::MaxStale(Some(s))
+            | CacheDirective::MinFresh(s)
+            | CacheDirective::SMaxAge(s)
+            | CacheDirective::StaleWhileRevalidate(s)
+            | CacheDirective::StaleIfError(s) => {
+                write!(f, "={}", s.as_secs().min(MAX_DELTA_SECONDS))
+            }
+            // always quoted, RFC 7234 section 5.2.2.2
+            CacheDirective::NoCache(names) | CacheDirective::Private(names)
+                if !names.is_empty() =>
+            {
+                write!(f, "=\"{}\"", names.join(", "))
+            }
+            CacheDirective::Extension(_, Some(argument)) => {
+                write!(f, "={}", quote(argument))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The `Cache-Control` header, RFC 7234 section 5.2, like
+/// `public, max-age=31536000, immutable`.
+///
+/// Directives keep their order. Directive names are matched
+/// case-insensitively and unknown ones are kept as extensions. Malformed
+/// directives are left out, so a parsed header may have none.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CacheControl {
+    directives: Vec<CacheDirective>,
+}
+
+impl CacheControl {
+    pub fn new() -> CacheControl {
+        CacheControl { directives: vec![] }
+    }
+
+    /// Adds `directive`, replacing an earlier one of the same name
+    pub fn with(mut self, directive: CacheDirective) -> CacheControl {
+        self.directives.retain(|d| d.name() != directive.name());
+        self.directives.push(directive);
+        self
+    }
+
+    pub fn directives(&self) -> &[CacheDirective] {
+        &self.directives
+    }
+
+    /// The first directive named `name`, ignoring case
+    pub fn get(&self, name: &str) -> Option<&CacheDirective> {
+        self.directives
+            .iter()
+            .find(|d| d.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn max_age(&self) -> Option<Duration> {
+        self.seconds("max-age")
+    }
+
+    pub fn s_maxage(&self) -> Option<Duration> {
+        self.seconds("s-maxage")
+    }
+
+    pub fn min_fresh(&self) -> Option<Duration> {
+        self.seconds("min-fresh")
+    }
+
+    pub fn stale_while_revalidate(&self) -> Option<Duration> {
+        self.seconds("stale-while-revalidate")
+    }
+
+    pub fn stale_if_error(&self) -> Option<Duration> {
+        self.seconds("stale-if-error")
+    }
+
+    /// `Some(None)` for `max-stale` without a limit
+    pub fn max_stale(&self) -> Option<Option<Duration>> {
+        match self.get("max-stale")? {
+            CacheDirective::MaxStale(limit) => Some(*limit),
+            _ => None,
+        }
+    }
+
+    /// Header fields `no-cache` is limited to, empty if it applies to the
+    /// whole response. `None` without `no-cache`.
+    pub fn no_cache(&self) -> Option<&[String]> {
+        match self.get("no-cache")? {
+            CacheDirective::NoCache(names) => Some(names),
+            _ => None,
+        }
+    }
+
+    /// Header fields `private` is limited to, empty if it applies to the
+    /// whole response. `None` without `private`.
+    pub fn private(&self) -> Option<&[String]> {
+        match self.get("private")? {
+            CacheDirective::Private(names) => Some(names),
+            _ => None,
+        }
+    }
+
+    fn seconds(&self, name: &str) -> Option<Duration> {
+        match self.get(name)? {
+            CacheDirective::MaxAge(s)
+            | CacheDirective::MinFresh(s)
+            | CacheDirective::SMaxAge(s)
+            | CacheDirective::StaleWhileRevalidate(s)
+            | CacheDirective::StaleIfError(s) => Some(*s),
+            _ => None,
+        }
+    }
+}
+
+impl TryFrom<&str> for CacheControl {
+    type Error = HttpErrors;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let directives = split_quoted(value, ',')
+            .into_iter()
+            .filter_map(|d| CacheDirective::parse(d).ok())
+            .collect();
+
+        Ok(CacheControl { directives })
+    }
+}
+
+impl TypedHeader for CacheControl {
+    const NAME: &'static str = CACHE_CONTROL_HEADER_NAME;
+
+    fn parse(value: &str) -> Result<Self, HttpErrors> {
+        CacheControl::try_from(value)
+    }
+
+    fn encode(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl GeneralHeader for CacheControl {}
+
+impl fmt::Display for CacheControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let directives: Vec<String> =
+            self.directives.iter().map(|d| d.to_string()).collect();
+        f.write_str(&directives.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests_cache_control {
+    use super::*;
+    use crate::assert_match_error;
+    use crate::request::RequestBuilder;
+
+    fn seconds(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    #[test]
+    fn test_parse_response_directives() {
+        let value = "Public, max-age=60, s-maxage=\"30\", must-revalidate, \
+                     proxy-revalidate, no-transform, immutable, \
+                     stale-while-revalidate=10, stale-if-error=20, \
+                     must-understand";
+        let header = CacheControl::try_from(value).unwrap();
+        assert_eq!(header.directives().len(), 10);
+        assert_eq!(header.directives()[0], CacheDirective::Public);
+        assert_eq!(header.max_age(), Some(seconds(60)));
+        assert_eq!(header.s_maxage(), Some(seconds(30)));
+        assert_eq!(header.stale_while_revalidate(), Some(seconds(10)));
+        assert_eq!(header.stale_if_error(), Some(seconds(20)));
+        assert!(header.contains("IMMUTABLE"));
+        assert!(header.contains("must-understand"));
+        assert!(!header.contains("no-store"));
+        assert_eq!(
+            header.encode(),
+            "public, max-age=60, s-maxage=30, must-revalidate, \
+             proxy-revalidate, no-transform, immutable, \
+             stale-while-revalidate=10, stale-if-error=20, must-understand"
+        );
+    }
+
+    #[test]
+    fn test_parse_request_directives() {
+        let value = "max-stale, min-fresh=5, only-if-cached, no-cache";
+        let header = CacheControl::try_from(value).unwrap();
+        assert_eq!(header.max_stale(), Some(None));
+        assert_eq!(header.min_fresh(), Some(seconds(5)));
+        assert!(header.contains("only-if-cached"));
+        assert_eq!(header.no_cache(), Some(&[][..]));
+        assert_eq!(header.private(), None);
+        assert_eq!(header.encode(), value);
+
+        let header = CacheControl::try_from("max-stale=30").unwrap();
+        assert_eq!(header.max_stale(), Some(Some(seconds(30))));
+    }
+
+    #[test]
+    fn test_field_names() {
+        let value = "no-cache=\"Set-Cookie, set-cookie2\", private=\"x-user\"";
+        let header = CacheControl::try_from(value).unwrap();
+        let names = vec!["set-cookie".to_string(), "set-cookie2".to_string()];
+        assert_eq!(header.no_cache(), Some(&names[..]));
+        assert_eq!(header.private(), Some(&["x-user".to_string()][..]));
+        assert_eq!(
+            header.encode(),
+            "no-cache=\"set-cookie, set-cookie2\", private=\"x-user\""
+        );
+
+        let header = CacheControl::try_from("no-cache=set-cookie").unwrap();
+        assert_eq!(header.encode(), "no-cache=\"set-cookie\"");
+    }
+
+    #[test]
+    fn test_extensions() {
+        let value = "community=\"UCI\", foo, max-age=soon, max-age=99999999999";
+        let header = CacheControl::try_from(value).unwrap();
+        assert_eq!(
+            header.directives()[..3],
+            [
+                CacheDirective::Extension(
+                    "community".into(),
+                    Some("UCI".into())
+                ),
+                CacheDirective::Extension("foo".into(), None),
+                CacheDirective::Extension(
+                    "max-age".into(),
+                    Some("soon".into())
+                ),
+            ]
+        );
+        assert_eq!(
+            header.directives()[3],
+            CacheDirective::MaxAge(seconds(MAX_DELTA_SECONDS))
+        );
+
+        // a malformed max-age shadows the valid one
+        assert_eq!(header.max_age(), None);
+        assert_eq!(
+            header.encode(),
+            "community=UCI, foo, max-age=soon, max-age=2147483648"
+        );
+
+        let extension =
+            CacheDirective::Extension("note".into(), Some("a b".into()));
+        assert_eq!(extension.to_string(), "note=\"a b\"");
+    }
+
+    #[test]
+    fn test_invalid() {
+        for value in ["", " , ", "max age=5", "private=\"a", "a=b c"] {
+            let header = CacheControl::try_from(value).unwrap();
+            assert!(header.directives().is_empty(), "{}", value);
+        }
+
+        let expected = HttpErrors::InvalidHeaderFieldValue("no cache".into());
+        let error = CacheDirective::parse("no cache").unwrap_err();
+        assert_match_error!(error, expected);
+
+        let header = CacheControl::try_from("no cache, max-age=5").unwrap();
+        assert_eq!(header.encode(), "max-age=5");
+    }
+
+    #[test]
+    fn test_request_with_invalid_directives() {
+        let buffer = "GET / HTTP/1.1\r\nCache-Control: max age=5, a=b c\r\n\
+                      Cache-Control: no-store\r\n\r\n";
+        let mut builder = RequestBuilder::new();
+        builder
+            .parse(buffer.as_bytes().to_vec(), buffer.len())
+            .unwrap();
+        let request = builder.build();
+
+        let header = request.headers().get_typed::<CacheControl>().unwrap();
+        assert!(header.directives().is_empty());
+        assert_eq!(request.headers().get_all(CacheControl::NAME).len(), 2);
+    }
+
+    #[test]
+    fn test_with() {
+        let header = CacheControl::new()
+            .with(CacheDirective::MaxAge(seconds(60)))
+            .with(CacheDirective::NoCache(vec!["set-cookie".into()]))
+            .with(CacheDirective::MaxAge(seconds(5)));
+        assert_eq!(header.encode(), "no-cache=\"set-cookie\", max-age=5");
+    }
+}
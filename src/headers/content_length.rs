@@ -1,6 +1,5 @@
 use crate::errors::Error as HttpErrors;
-use crate::headers::{EntityHeader, Header, CONTENT_LENGTH_HEADER_NAME};
-use std::any::Any;
+use crate::headers::{EntityHeader, TypedHeader, CONTENT_LENGTH_HEADER_NAME};
 use std::convert::TryFrom;
 use std::str;
 
@@ -22,22 +21,26 @@ impl TryFrom<&str> for ContentLength {
 }
 
 impl ContentLength {
+    pub fn new(length: usize) -> ContentLength {
+        ContentLength {
+            length: length.to_string(),
+        }
+    }
+
     pub fn len(&self) -> usize {
         self.length.parse::<usize>().unwrap()
     }
 }
 
-impl Header for ContentLength {
-    fn name(&self) -> &str {
-        CONTENT_LENGTH_HEADER_NAME
-    }
+impl TypedHeader for ContentLength {
+    const NAME: &'static str = CONTENT_LENGTH_HEADER_NAME;
 
-    fn value(&self) -> String {
-        self.length.clone()
+    fn parse(value: &str) -> Result<Self, HttpErrors> {
+        ContentLength::try_from(value)
     }
 
-    fn as_any(&self) -> &dyn Any {
-        self
+    fn encode(&self) -> String {
+        self.length.clone()
     }
 }
 
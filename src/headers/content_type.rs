@@ -0,0 +1,231 @@
+use crate::errors::Error as HttpErrors;
+use crate::grammar::is_token;
+use crate::headers::parameters::{parse_parameter, quote, split_quoted};
+use crate::headers::{EntityHeader, TypedHeader, CONTENT_TYPE_HEADER_NAME};
+use std::convert::TryFrom;
+use std::fmt;
+
+/// The `Content-Type` header, RFC 7231 section 3.1.1.5, like
+/// `text/html; charset=utf-8`.
+///
+/// Type, subtype and parameter names are lower-cased. Parameter values keep
+/// their case and are compared case-sensitively, except for `charset`.
+/// A malformed field of a request is kept as it was received, and
+/// [`Headers::content_type`](crate::headers::Headers::content_type) returns
+/// `None` for it.
+#[derive(Clone, Debug)]
+pub struct ContentType {
+    typ: String,
+    sub_type: String,
+    params: Vec<(String, String)>,
+}
+
+impl ContentType {
+    /// Media type without parameters, `typ` and `sub_type` have to be tokens
+    pub fn new(typ: &str, sub_type: &str) -> ContentType {
+        ContentType {
+            typ: typ.to_ascii_lowercase(),
+            sub_type: sub_type.to_ascii_lowercase(),
+            params: vec![],
+        }
+    }
+
+    /// Sets the parameter `name`, replacing an earlier value
+    pub fn with_param(mut self, name: &str, value: &str) -> ContentType {
+        let name = name.to_ascii_lowercase();
+        self.params.retain(|(n, _)| n != &name);
+        self.params.push((name, value.to_string()));
+        self
+    }
+
+    pub fn typ(&self) -> &str {
+        &self.typ
+    }
+
+    pub fn sub_type(&self) -> &str {
+        &self.sub_type
+    }
+
+    /// `type/subtype` without parameters
+    pub fn essence(&self) -> String {
+        format!("{}/{}", self.typ, self.sub_type)
+    }
+
+    /// Whether the media type is `media_type`, e.g. `is("application/json")`,
+    /// ignoring case and parameters
+    pub fn is(&self, media_type: &str) -> bool {
+        match media_type.split_once('/') {
+            None => false,
+            Some((typ, sub_type)) => {
+                self.typ.eq_ignore_ascii_case(typ)
+                    && self.sub_type.eq_ignore_ascii_case(sub_type)
+            }
+        }
+    }
+
+    pub fn params(&self) -> &[(String, String)] {
+        &self.params
+    }
+
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn charset(&self) -> Option<&str> {
+        self.param("charset")
+    }
+
+    /// Delimiter of the parts of a `multipart/*` body
+    pub fn boundary(&self) -> Option<&str> {
+        self.param("boundary")
+    }
+}
+
+impl PartialEq for ContentType {
+    fn eq(&self, other: &ContentType) -> bool {
+        let param_eq = |(name, value): &(String, String)| {
+            other.param(name).is_some_and(|v| match name.as_str() {
+                "charset" => v.eq_ignore_ascii_case(value),
+                _ => v == value,
+            })
+        };
+
+        self.typ == other.typ
+            && self.sub_type == other.sub_type
+            && self.params.len() == other.params.len()
+            && self.params.iter().all(param_eq)
+    }
+}
+
+impl TryFrom<&str> for ContentType {
+    type Error = HttpErrors;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let invalid = || HttpErrors::InvalidHeaderFieldValue(value.to_string());
+
+        let mut parts = split_quoted(value, ';').into_iter();
+        let media_type = parts.next().ok_or_else(invalid)?;
+        let (typ, sub_type) = media_type.split_once('/').ok_or_else(invalid)?;
+
+        let is_valid = |s: &str| !s.is_empty() && is_token(s.as_bytes());
+        if !is_valid(typ) || !is_valid(sub_type) {
+            return Err(invalid());
+        }
+
+        let mut content_type = ContentType::new(typ, sub_type);
+        for parameter in parts {
+            let (name, value) = parse_parameter(parameter)?;
+            content_type.params.push((name, value));
+        }
+
+        Ok(content_type)
+    }
+}
+
+impl TypedHeader for ContentType {
+    const NAME: &'static str = CONTENT_TYPE_HEADER_NAME;
+
+    fn parse(value: &str) -> Result<Self, HttpErrors> {
+        ContentType::try_from(value)
+    }
+
+    fn encode(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl EntityHeader for ContentType {}
+
+impl fmt::Display for ContentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.typ, self.sub_type)?;
+        for (name, value) in self.params.iter() {
+            write!(f, "; {}={}", name, quote(value))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests_content_type {
+    use super::*;
+    use crate::request::RequestBuilder;
+
+    #[test]
+    fn test_parse() {
+        let content_type =
+            ContentType::try_from("Text/HTML; Charset=\"UTF-8\"").unwrap();
+        assert_eq!(content_type.typ(), "text");
+        assert_eq!(content_type.sub_type(), "html");
+        assert_eq!(content_type.essence(), "text/html");
+        assert_eq!(content_type.charset(), Some("UTF-8"));
+        assert!(content_type.is("text/html"));
+        assert!(content_type.is("TEXT/Html"));
+        assert!(!content_type.is("text/plain"));
+
+        let content_type = ContentType::try_from(
+            "multipart/form-data;boundary=\"a;b \\\"c\\\"\"",
+        )
+        .unwrap();
+        assert_eq!(content_type.boundary(), Some("a;b \"c\""));
+        assert_eq!(content_type.charset(), None);
+    }
+
+    #[test]
+    fn test_invalid() {
+        for value in
+            ["", "text", "text/", "/html", "text/html;charset", "a b/c"].iter()
+        {
+            assert!(ContentType::try_from(*value).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_request_with_invalid_content_type() {
+        for value in ["text", "text/html; charset"] {
+            let buffer =
+                format!("GET / HTTP/1.1\r\nContent-Type: {}\r\n\r\n", value);
+            let mut builder = RequestBuilder::new();
+            builder
+                .parse(buffer.as_bytes().to_vec(), buffer.len())
+                .unwrap();
+            let request = builder.build();
+
+            let headers = request.headers();
+            assert!(headers.content_type().is_none(), "{}", value);
+            assert_eq!(headers.get(ContentType::NAME).unwrap().value(), value);
+        }
+    }
+
+    #[test]
+    fn test_comparison() {
+        let parsed = ContentType::try_from("TEXT/plain;CHARSET=utf-8").unwrap();
+        let built =
+            ContentType::new("text", "plain").with_param("charset", "UTF-8");
+        assert_eq!(parsed, built);
+
+        let a = ContentType::try_from("multipart/mixed; boundary=abc").unwrap();
+        let b = ContentType::try_from("multipart/mixed; boundary=ABC").unwrap();
+        assert_ne!(a, b);
+        assert_ne!(a, ContentType::new("multipart", "mixed"));
+    }
+
+    #[test]
+    fn test_encode() {
+        let content_type = ContentType::new("application", "json")
+            .with_param("charset", "utf-8")
+            .with_param("note", "a \"b\"")
+            .with_param("Charset", "latin1");
+        assert_eq!(
+            content_type.encode(),
+            "application/json; note=\"a \\\"b\\\"\"; charset=latin1"
+        );
+
+        let round_trip = ContentType::try_from(content_type.encode().as_str());
+        assert_eq!(round_trip.unwrap(), content_type);
+    }
+}
@@ -0,0 +1,543 @@
+use crate::errors::Error as HttpErrors;
+use crate::grammar::{
+    format_http_date, is_token, is_visible_char, parse_http_date,
+};
+use crate::headers::{
+    RequestHeader, ResponseHeader, TypedHeader, COOKIE_HEADER_NAME,
+};
+use std::convert::TryFrom;
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SET_COOKIE_HEADER_NAME: &str = "set-cookie";
+
+/// The `Cookie` request header, RFC 6265 section 5.4: the name/value pairs
+/// the user agent stored for the request, in the order it sent them
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Cookie {
+    pairs: Vec<(String, String)>,
+}
+
+impl Cookie {
+    pub fn new() -> Self {
+        Cookie { pairs: vec![] }
+    }
+
+    /// Adds a pair, for requests sent by a client
+    pub fn add(&mut self, name: &str, value: &str) {
+        self.pairs.push((name.to_string(), value.to_string()));
+    }
+
+    /// Value of the first cookie named `name`. Names are case-sensitive.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Values of all cookies named `name`. Cookies with the same name but a
+    /// different path or domain are all sent, most specific path first.
+    pub fn get_all(&self, name: &str) -> Vec<&str> {
+        self.pairs
+            .iter()
+            .filter(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pairs.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+}
+
+impl TryFrom<&str> for Cookie {
+    type Error = HttpErrors;
+
+    /// Pairs without a "=" or with an invalid name are skipped instead of
+    /// failing the request, user agents send whatever was stored
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut cookie = Cookie::new();
+
+        for pair in value.split(';') {
+            let (name, value) = match pair.split_once('=') {
+                Some((n, v)) => (n.trim(), v.trim()),
+                None => continue,
+            };
+
+            if name.is_empty() || !is_token(name.as_bytes()) {
+                continue;
+            }
+
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(value);
+            cookie.add(name, value);
+        }
+
+        Ok(cookie)
+    }
+}
+
+impl TypedHeader for Cookie {
+    const NAME: &'static str = COOKIE_HEADER_NAME;
+
+    fn parse(value: &str) -> Result<Self, HttpErrors> {
+        Cookie::try_from(value)
+    }
+
+    fn encode(&self) -> String {
+        let pairs: Vec<String> = self
+            .pairs
+            .iter()
+            .map(|(n, v)| format!("{}={}", n, v))
+            .collect();
+
+        pairs.join("; ")
+    }
+}
+
+impl RequestHeader for Cookie {}
+
+/// Value of the `SameSite` attribute
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl SameSite {
+    fn as_str(&self) -> &str {
+        match self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        }
+    }
+}
+
+/// The `Set-Cookie` response header, RFC 6265 section 4.1. Every cookie is
+/// sent in a header line of its own, see
+/// [`Response::add_cookie`](crate::response::Response::add_cookie).
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetCookie {
+    name: String,
+    value: String,
+    expires: Option<SystemTime>,
+    max_age: Option<Duration>,
+    domain: Option<String>,
+    path: Option<String>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+    partitioned: bool,
+}
+
+impl SetCookie {
+    pub fn builder(name: &str, value: &str) -> SetCookieBuilder {
+        SetCookieBuilder {
+            cookie: SetCookie {
+                name: name.to_string(),
+                value: value.to_string(),
+                expires: None,
+                max_age: None,
+                domain: None,
+                path: None,
+                secure: false,
+                http_only: false,
+                same_site: None,
+                partitioned: false,
+            },
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn expires(&self) -> Option<SystemTime> {
+        self.expires
+    }
+
+    pub fn max_age(&self) -> Option<Duration> {
+        self.max_age
+    }
+
+    pub fn domain(&self) -> Option<&str> {
+        self.domain.as_deref()
+    }
+
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    pub fn secure(&self) -> bool {
+        self.secure
+    }
+
+    pub fn http_only(&self) -> bool {
+        self.http_only
+    }
+
+    pub fn same_site(&self) -> Option<SameSite> {
+        self.same_site
+    }
+
+    pub fn partitioned(&self) -> bool {
+        self.partitioned
+    }
+
+    /// The same cookie with another value, which has to be a valid
+    /// cookie-value
+    pub(crate) fn with_value(mut self, value: String) -> SetCookie {
+        self.value = value;
+        self
+    }
+
+    /// A cookie telling the user agent to delete this one. Name, domain
+    /// and path stay the same, as the user agent matches on all three.
+    pub(crate) fn into_removal(mut self) -> SetCookie {
+        self.value = String::new();
+        self.expires = Some(UNIX_EPOCH);
+        self.max_age = Some(Duration::from_secs(0));
+        self
+    }
+}
+
+impl TryFrom<&str> for SetCookie {
+    type Error = HttpErrors;
+
+    /// Parses the header as a user agent would, RFC 6265 section 5.2.
+    /// Attributes which are unknown or have an invalid value are ignored.
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let invalid = || HttpErrors::InvalidCookie(value.to_string());
+
+        let mut parts = value.split(';');
+        let pair = parts.next().unwrap_or_default();
+        let (name, cookie_value) = pair.split_once('=').ok_or_else(invalid)?;
+        let mut builder = SetCookie::builder(name.trim(), cookie_value.trim());
+
+        for attribute in parts {
+            let (name, value) = match attribute.split_once('=') {
+                Some((n, v)) => (n.trim(), v.trim()),
+                None => (attribute.trim(), ""),
+            };
+
+            builder = match name.to_ascii_lowercase().as_str() {
+                "expires" => match parse_http_date(value) {
+                    Some(time) => builder.expires(time),
+                    None => builder,
+                },
+                "max-age" => match value.parse::<i64>() {
+                    Ok(seconds) => {
+                        let seconds = seconds.max(0) as u64;
+                        builder.max_age(Duration::from_secs(seconds))
+                    }
+                    Err(_) => builder,
+                },
+                "domain" if !value.is_empty() => {
+                    let domain = value.trim_start_matches('.');
+                    builder.domain(&domain.to_ascii_lowercase())
+                }
+                "path" if value.starts_with('/') => builder.path(value),
+                "secure" => builder.secure(true),
+                "httponly" => builder.http_only(true),
+                "samesite" => match value.to_ascii_lowercase().as_str() {
+                    "strict" => builder.same_site(SameSite::Strict),
+                    "lax" => builder.same_site(SameSite::Lax),
+                    "none" => builder.same_site(SameSite::None),
+                    _ => builder,
+                },
+                "partitioned" => builder.partitioned(true),
+                _ => builder,
+            };
+        }
+
+        builder.build()
+    }
+}
+
+impl TypedHeader for SetCookie {
+    const NAME: &'static str = SET_COOKIE_HEADER_NAME;
+
+    fn parse(value: &str) -> Result<Self, HttpErrors> {
+        SetCookie::try_from(value)
+    }
+
+    fn encode(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl ResponseHeader for SetCookie {}
+
+impl fmt::Display for SetCookie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+
+        if let Some(expires) = self.expires {
+            write!(f, "; Expires={}", format_http_date(expires))?;
+        }
+
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={}", max_age.as_secs())?;
+        }
+
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={}", domain)?;
+        }
+
+        if let Some(path) = &self.path {
+            write!(f, "; Path={}", path)?;
+        }
+
+        if self.secure {
+            f.write_str("; Secure")?;
+        }
+
+        if self.http_only {
+            f.write_str("; HttpOnly")?;
+        }
+
+        if let Some(same_site) = self.same_site {
+            write!(f, "; SameSite={}", same_site.as_str())?;
+        }
+
+        if self.partitioned {
+            f.write_str("; Partitioned")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Builder of [`SetCookie`], which checks the cookie when it is built
+pub struct SetCookieBuilder {
+    cookie: SetCookie,
+}
+
+impl SetCookieBuilder {
+    /// Point in time after which the cookie is deleted
+    pub fn expires(mut self, time: SystemTime) -> Self {
+        self.cookie.expires = Some(time);
+        self
+    }
+
+    /// Lifetime of the cookie, which takes precedence over `expires`. A
+    /// zero duration deletes the cookie.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.cookie.max_age = Some(max_age);
+        self
+    }
+
+    pub fn domain(mut self, domain: &str) -> Self {
+        self.cookie.domain = Some(domain.to_string());
+        self
+    }
+
+    pub fn path(mut self, path: &str) -> Self {
+        self.cookie.path = Some(path.to_string());
+        self
+    }
+
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.cookie.secure = secure;
+        self
+    }
+
+    pub fn http_only(mut self, http_only: bool) -> Self {
+        self.cookie.http_only = http_only;
+        self
+    }
+
+    /// Browsers reject `SameSite=None` without `Secure`, so it turns
+    /// `secure` on as well
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.cookie.same_site = Some(same_site);
+        if same_site == SameSite::None {
+            self.cookie.secure = true;
+        }
+
+        self
+    }
+
+    /// Asks for a cookie jar partitioned by the top-level site (CHIPS).
+    /// Like `SameSite=None`, it requires and turns on `secure`.
+    pub fn partitioned(mut self, partitioned: bool) -> Self {
+        self.cookie.partitioned = partitioned;
+        if partitioned {
+            self.cookie.secure = true;
+        }
+
+        self
+    }
+
+    /// Checks that the name is a token and that the value, domain and path
+    /// cannot break out of their attribute
+    pub fn build(self) -> Result<SetCookie, HttpErrors> {
+        let cookie = self.cookie;
+        let invalid = |part: &str| {
+            HttpErrors::InvalidCookie(format!("{} in {}", part, cookie.name))
+        };
+
+        if cookie.name.is_empty() || !is_token(cookie.name.as_bytes()) {
+            return Err(HttpErrors::InvalidCookie(cookie.name.clone()));
+        }
+
+        if !is_cookie_value(&cookie.value) {
+            return Err(invalid("value"));
+        }
+
+        let is_attribute_value = |value: &Option<String>| match value {
+            None => true,
+            Some(v) => v.bytes().all(|b| is_visible_char(b) && b != b';'),
+        };
+
+        if !is_attribute_value(&cookie.domain) {
+            return Err(invalid("domain"));
+        }
+
+        if !is_attribute_value(&cookie.path) {
+            return Err(invalid("path"));
+        }
+
+        Ok(cookie)
+    }
+}
+
+/// cookie-value = *cookie-octet / ( DQUOTE *cookie-octet DQUOTE )
+///
+/// cookie-octet = %x21 / %x23-2B / %x2D-3A / %x3C-5B / %x5D-7E
+fn is_cookie_value(value: &str) -> bool {
+    let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value);
+
+    value.bytes().all(|b| {
+        is_visible_char(b) && b != b'"' && b != b',' && b != b';' && b != b'\\'
+    })
+}
+
+#[cfg(test)]
+mod tests_cookie {
+    use super::*;
+    use crate::assert_match_error;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn test_parse_cookie() {
+        let cookie =
+            Cookie::try_from("SID=31d4d96e407aad42; lang=en-US; lang=\"de\"")
+                .unwrap();
+        assert_eq!(cookie.len(), 3);
+        assert_eq!(cookie.get("SID"), Some("31d4d96e407aad42"));
+        assert_eq!(cookie.get("sid"), None);
+        assert_eq!(cookie.get_all("lang"), vec!["en-US", "de"]);
+
+        let pairs: Vec<(&str, &str)> = cookie.iter().collect();
+        assert_eq!(pairs[0], ("SID", "31d4d96e407aad42"));
+    }
+
+    #[test]
+    fn test_parse_cookie_skips_invalid_pairs() {
+        let cookie = Cookie::try_from("a=1;; junk; =2; b c=3; d=;e=5").unwrap();
+        let pairs: Vec<(&str, &str)> = cookie.iter().collect();
+        assert_eq!(pairs, vec![("a", "1"), ("d", ""), ("e", "5")]);
+        assert_eq!(cookie.encode(), "a=1; d=; e=5");
+    }
+
+    #[test]
+    fn test_build_set_cookie() {
+        let expires = UNIX_EPOCH + Duration::from_secs(1445412480);
+        let cookie = SetCookie::builder("id", "a3fWa")
+            .expires(expires)
+            .max_age(Duration::from_secs(2592000))
+            .domain("example.com")
+            .path("/docs")
+            .secure(true)
+            .http_only(true)
+            .same_site(SameSite::Strict)
+            .partitioned(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            cookie.encode(),
+            "id=a3fWa; Expires=Wed, 21 Oct 2015 07:28:00 GMT; \
+             Max-Age=2592000; Domain=example.com; Path=/docs; Secure; \
+             HttpOnly; SameSite=Strict; Partitioned"
+        );
+    }
+
+    #[test]
+    fn test_set_cookie_requires_secure() {
+        let cookie = SetCookie::builder("a", "1")
+            .same_site(SameSite::None)
+            .build()
+            .unwrap();
+        assert_eq!(cookie.encode(), "a=1; Secure; SameSite=None");
+
+        let cookie = SetCookie::builder("a", "1")
+            .partitioned(true)
+            .build()
+            .unwrap();
+        assert_eq!(cookie.encode(), "a=1; Secure; Partitioned");
+    }
+
+    #[test]
+    fn test_invalid_set_cookie() {
+        let result = SetCookie::builder("a", "1; Domain=evil.com").build();
+        let expected_error = HttpErrors::InvalidCookie("value in a".into());
+        assert_match_error!(result.err().unwrap(), expected_error);
+
+        assert!(SetCookie::builder("", "1").build().is_err());
+        assert!(SetCookie::builder("a b", "1").build().is_err());
+        assert!(SetCookie::builder("a", "x y").build().is_err());
+        assert!(SetCookie::builder("a", "1").path("/;x").build().is_err());
+        assert!(SetCookie::builder("a", "1")
+            .domain("a\r\n")
+            .build()
+            .is_err());
+        assert!(SetCookie::builder("a", "\"quoted\"").build().is_ok());
+    }
+
+    #[test]
+    fn test_parse_set_cookie() {
+        let cookie = SetCookie::try_from(
+            "id=a3fWa; expires=Wed, 21 Oct 2015 07:28:00 GMT; max-age=-5; \
+             Domain=.Example.com; Path=docs; secure; HTTPONLY; \
+             SameSite=lax; Unknown=1",
+        )
+        .unwrap();
+
+        assert_eq!(cookie.name(), "id");
+        assert_eq!(cookie.value(), "a3fWa");
+        let expires = UNIX_EPOCH + Duration::from_secs(1445412480);
+        assert_eq!(cookie.expires(), Some(expires));
+        assert_eq!(cookie.max_age(), Some(Duration::from_secs(0)));
+        assert_eq!(cookie.domain(), Some("example.com"));
+        assert_eq!(cookie.path(), None);
+        assert!(cookie.secure());
+        assert!(cookie.http_only());
+        assert_eq!(cookie.same_site(), Some(SameSite::Lax));
+        assert!(!cookie.partitioned());
+
+        assert!(SetCookie::try_from("no-pair").is_err());
+        assert!(SetCookie::try_from("=value").is_err());
+    }
+}
@@ -0,0 +1,376 @@
+use crate::errors::Error as HttpErrors;
+use crate::headers::{
+    RequestHeader, ResponseHeader, TypedHeader, ETAG_HEADER_NAME,
+    IF_MATCH_HEADER_NAME, IF_NONE_MATCH_HEADER_NAME,
+};
+use std::convert::TryFrom;
+use std::fmt;
+
+/// An entity-tag, RFC 7232 section 2.3, like `"xyzzy"` or `W/"xyzzy"`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntityTag {
+    weak: bool,
+    tag: String,
+}
+
+impl EntityTag {
+    /// Strong entity-tag, `tag` is the opaque-tag without the quotes
+    pub fn strong(tag: &str) -> Result<EntityTag, HttpErrors> {
+        EntityTag::new(false, tag)
+    }
+
+    /// Weak entity-tag, for representations which are only semantically
+    /// equivalent, e.g. differently compressed
+    pub fn weak(tag: &str) -> Result<EntityTag, HttpErrors> {
+        EntityTag::new(true, tag)
+    }
+
+    fn new(weak: bool, tag: &str) -> Result<EntityTag, HttpErrors> {
+        if !tag.chars().all(is_etag_char) {
+            return Err(HttpErrors::InvalidEntityTag(tag.to_string()));
+        }
+
+        Ok(EntityTag {
+            weak,
+            tag: tag.to_string(),
+        })
+    }
+
+    pub fn is_weak(&self) -> bool {
+        self.weak
+    }
+
+    /// The opaque-tag without the quotes
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Strong comparison, RFC 7232 section 2.3.2: both tags are strong and
+    /// the same
+    pub fn strong_eq(&self, other: &EntityTag) -> bool {
+        !self.weak && !other.weak && self.tag == other.tag
+    }
+
+    /// Weak comparison, RFC 7232 section 2.3.2: the tags are the same,
+    /// whether weak or not
+    pub fn weak_eq(&self, other: &EntityTag) -> bool {
+        self.tag == other.tag
+    }
+}
+
+/// etagc = %x21 / %x23-7E / obs-text
+fn is_etag_char(c: char) -> bool {
+    c == '!' || ('#'..='~').contains(&c) || !c.is_ascii()
+}
+
+/// Parses the entity-tag at the start of `value`, returning the rest
+fn parse_entity_tag(value: &str) -> Option<(EntityTag, &str)> {
+    let (weak, value) = match value.strip_prefix("W/") {
+        Some(value) => (true, value),
+        None => (false, value),
+    };
+
+    let value = value.strip_prefix('"')?;
+    let end = value.find('"')?;
+    let tag = EntityTag::new(weak, &value[..end]).ok()?;
+    Some((tag, &value[end + 1..]))
+}
+
+impl TryFrom<&str> for EntityTag {
+    type Error = HttpErrors;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match parse_entity_tag(value.trim()) {
+            Some((tag, "")) => Ok(tag),
+            _ => Err(HttpErrors::InvalidEntityTag(value.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for EntityTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.weak {
+            f.write_str("W/")?;
+        }
+
+        write!(f, "\"{}\"", self.tag)
+    }
+}
+
+/// `"*" / 1#entity-tag`, `None` standing for `*`. Entity-tags may contain
+/// commas, so the list cannot simply be split.
+fn parse_entity_tag_list(
+    value: &str,
+) -> Result<Option<Vec<EntityTag>>, HttpErrors> {
+    let value = value.trim();
+    if value == "*" {
+        return Ok(None);
+    }
+
+    let invalid = || HttpErrors::InvalidEntityTag(value.to_string());
+    let mut tags = vec![];
+    let mut rest = value;
+    loop {
+        rest = rest.trim_start_matches([',', ' ', '\t']);
+        if rest.is_empty() {
+            break;
+        }
+
+        let (tag, after) = parse_entity_tag(rest).ok_or_else(invalid)?;
+        let after = after.trim_start_matches([' ', '\t']);
+        if !after.is_empty() && !after.starts_with(',') {
+            return Err(invalid());
+        }
+
+        tags.push(tag);
+        rest = after;
+    }
+
+    if tags.is_empty() {
+        return Err(invalid());
+    }
+
+    Ok(Some(tags))
+}
+
+fn format_entity_tag_list(tags: &[EntityTag]) -> String {
+    let tags: Vec<String> = tags.iter().map(|t| t.to_string()).collect();
+    tags.join(", ")
+}
+
+/// The `ETag` header, RFC 7232 section 2.3. A field-value which is not an
+/// entity-tag is kept with no entity-tag rather than rejected.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ETag {
+    tag: Option<EntityTag>,
+    value: String,
+}
+
+impl ETag {
+    pub fn new(tag: EntityTag) -> ETag {
+        ETag {
+            value: tag.to_string(),
+            tag: Some(tag),
+        }
+    }
+
+    /// `None` if the field-value was not an entity-tag
+    pub fn entity_tag(&self) -> Option<&EntityTag> {
+        self.tag.as_ref()
+    }
+}
+
+impl TypedHeader for ETag {
+    const NAME: &'static str = ETAG_HEADER_NAME;
+
+    fn parse(value: &str) -> Result<Self, HttpErrors> {
+        let value = value.trim();
+        Ok(ETag {
+            tag: EntityTag::try_from(value).ok(),
+            value: value.to_string(),
+        })
+    }
+
+    fn encode(&self) -> String {
+        self.value.clone()
+    }
+}
+
+impl ResponseHeader for ETag {}
+
+/// The `If-Match` header, RFC 7232 section 3.1. A value which is neither
+/// `*` nor a list of entity-tags is kept as `Invalid`, and ignored by
+/// [`Validators::evaluate`](crate::headers::Validators::evaluate).
+#[derive(Clone, Debug, PartialEq)]
+pub enum IfMatch {
+    Any,
+    Tags(Vec<EntityTag>),
+    Invalid(String),
+}
+
+impl IfMatch {
+    /// Whether the condition holds for the current representation, whose
+    /// entity-tag is `current`, using the strong comparison. `*` holds for
+    /// any representation, even one without entity-tag, `Invalid` for
+    /// none.
+    pub fn matches(&self, current: Option<&EntityTag>) -> bool {
+        match (self, current) {
+            (IfMatch::Any, _) => true,
+            (IfMatch::Tags(_), None) | (IfMatch::Invalid(_), _) => false,
+            (IfMatch::Tags(tags), Some(current)) => {
+                tags.iter().any(|t| t.strong_eq(current))
+            }
+        }
+    }
+}
+
+impl TypedHeader for IfMatch {
+    const NAME: &'static str = IF_MATCH_HEADER_NAME;
+
+    fn parse(value: &str) -> Result<Self, HttpErrors> {
+        Ok(match parse_entity_tag_list(value) {
+            Ok(tags) => tags.map_or(IfMatch::Any, IfMatch::Tags),
+            Err(_) => IfMatch::Invalid(value.trim().to_string()),
+        })
+    }
+
+    fn encode(&self) -> String {
+        match self {
+            IfMatch::Any => "*".to_string(),
+            IfMatch::Tags(tags) => format_entity_tag_list(tags),
+            IfMatch::Invalid(value) => value.clone(),
+        }
+    }
+}
+
+impl RequestHeader for IfMatch {}
+
+/// The `If-None-Match` header, RFC 7232 section 3.2. A value which is
+/// neither `*` nor a list of entity-tags is kept as `Invalid`, and ignored
+/// by [`Validators::evaluate`](crate::headers::Validators::evaluate).
+#[derive(Clone, Debug, PartialEq)]
+pub enum IfNoneMatch {
+    Any,
+    Tags(Vec<EntityTag>),
+    Invalid(String),
+}
+
+impl IfNoneMatch {
+    /// Whether any of the tags matches the entity-tag of the current
+    /// representation using the weak comparison, in which case the
+    /// condition does not hold. `*` matches any representation, `Invalid`
+    /// none.
+    pub fn matches(&self, current: Option<&EntityTag>) -> bool {
+        match (self, current) {
+            (IfNoneMatch::Any, _) => true,
+            (IfNoneMatch::Tags(_), None) | (IfNoneMatch::Invalid(_), _) => {
+                false
+            }
+            (IfNoneMatch::Tags(tags), Some(current)) => {
+                tags.iter().any(|t| t.weak_eq(current))
+            }
+        }
+    }
+}
+
+impl TypedHeader for IfNoneMatch {
+    const NAME: &'static str = IF_NONE_MATCH_HEADER_NAME;
+
+    fn parse(value: &str) -> Result<Self, HttpErrors> {
+        Ok(match parse_entity_tag_list(value) {
+            Ok(tags) => tags.map_or(IfNoneMatch::Any, IfNoneMatch::Tags),
+            Err(_) => IfNoneMatch::Invalid(value.trim().to_string()),
+        })
+    }
+
+    fn encode(&self) -> String {
+        match self {
+            IfNoneMatch::Any => "*".to_string(),
+            IfNoneMatch::Tags(tags) => format_entity_tag_list(tags),
+            IfNoneMatch::Invalid(value) => value.clone(),
+        }
+    }
+}
+
+impl RequestHeader for IfNoneMatch {}
+
+#[cfg(test)]
+mod tests_entity_tag {
+    use super::*;
+    use crate::assert_match_error;
+
+    fn strong(tag: &str) -> EntityTag {
+        EntityTag::strong(tag).unwrap()
+    }
+
+    fn weak(tag: &str) -> EntityTag {
+        EntityTag::weak(tag).unwrap()
+    }
+
+    #[test]
+    fn test_parse_entity_tag() {
+        assert_eq!(EntityTag::try_from("\"xyzzy\"").unwrap(), strong("xyzzy"));
+        assert_eq!(EntityTag::try_from("W/\"xyzzy\"").unwrap(), weak("xyzzy"));
+        assert_eq!(EntityTag::try_from("\"\"").unwrap(), strong(""));
+        assert_eq!(weak("a,b").to_string(), "W/\"a,b\"");
+
+        for value in ["xyzzy", "\"xyzzy", "w/\"xyzzy\"", "\"a\"b", "\"a b\""] {
+            let expected = HttpErrors::InvalidEntityTag(value.to_string());
+            assert_match_error!(
+                EntityTag::try_from(value).unwrap_err(),
+                expected
+            );
+        }
+
+        assert!(EntityTag::strong("a\"b").is_err());
+    }
+
+    #[test]
+    fn test_comparison() {
+        // RFC 7232 section 2.3.2
+        assert!(!weak("1").strong_eq(&weak("1")));
+        assert!(weak("1").weak_eq(&weak("1")));
+        assert!(!weak("1").strong_eq(&weak("2")));
+        assert!(!weak("1").weak_eq(&weak("2")));
+        assert!(!weak("1").strong_eq(&strong("1")));
+        assert!(weak("1").weak_eq(&strong("1")));
+        assert!(strong("1").strong_eq(&strong("1")));
+        assert!(strong("1").weak_eq(&strong("1")));
+    }
+
+    #[test]
+    fn test_entity_tag_lists() {
+        let header = IfNoneMatch::parse("\"a\", W/\"b,c\" ,\"d\"").unwrap();
+        assert_eq!(
+            header,
+            IfNoneMatch::Tags(vec![strong("a"), weak("b,c"), strong("d")])
+        );
+        assert_eq!(header.encode(), "\"a\", W/\"b,c\", \"d\"");
+
+        assert_eq!(IfMatch::parse(" * ").unwrap(), IfMatch::Any);
+        assert_eq!(IfMatch::Any.encode(), "*");
+        assert_eq!(IfMatch::parse("\"a\",").unwrap().encode(), "\"a\"");
+
+        for value in ["", ",", "\"a\" \"b\"", "*, \"a\"", "a"] {
+            let header = IfMatch::parse(value).unwrap();
+            assert_eq!(header, IfMatch::Invalid(value.into()));
+            assert_eq!(header.encode(), value);
+        }
+
+        let header = IfNoneMatch::parse(" \"a\", W/b ").unwrap();
+        assert_eq!(header, IfNoneMatch::Invalid("\"a\", W/b".into()));
+    }
+
+    #[test]
+    fn test_etag() {
+        let header = ETag::parse(" W/\"a\" ").unwrap();
+        assert_eq!(header.entity_tag(), Some(&weak("a")));
+        assert_eq!(header, ETag::new(weak("a")));
+
+        let header = ETag::parse("abc").unwrap();
+        assert_eq!(header.entity_tag(), None);
+        assert_eq!(header.encode(), "abc");
+    }
+
+    #[test]
+    fn test_matches() {
+        let current = strong("a");
+        let if_match = IfMatch::Tags(vec![weak("a"), strong("b")]);
+        assert!(!if_match.matches(Some(&current)));
+        assert!(IfMatch::Tags(vec![strong("a")]).matches(Some(&current)));
+        assert!(IfMatch::Any.matches(Some(&current)));
+        assert!(IfMatch::Any.matches(None));
+        assert!(!IfMatch::Tags(vec![strong("a")]).matches(None));
+
+        let if_none_match = IfNoneMatch::Tags(vec![weak("a")]);
+        assert!(if_none_match.matches(Some(&current)));
+        assert!(!if_none_match.matches(Some(&strong("b"))));
+        assert!(IfNoneMatch::Any.matches(Some(&current)));
+        assert!(IfNoneMatch::Any.matches(None));
+        assert!(!if_none_match.matches(None));
+
+        let invalid = IfMatch::Invalid("a".into());
+        assert!(!invalid.matches(Some(&current)));
+        assert!(!IfNoneMatch::Invalid("a".into()).matches(Some(&current)));
+    }
+}
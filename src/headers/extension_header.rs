@@ -1,4 +1,4 @@
-use crate::headers::{EntityHeader, Header, EXTENSION_HEADER_NAME};
+use crate::headers::{EntityHeader, Header};
 use std::any::Any;
 use std::marker::PhantomData;
 
@@ -19,7 +19,7 @@ impl ExtensionHeader {
 
 impl Header for ExtensionHeader {
     fn name(&self) -> &str {
-        EXTENSION_HEADER_NAME
+        &self.name
     }
 
     fn value(&self) -> String {
@@ -0,0 +1,157 @@
+use crate::errors::Error as HttpErrors;
+use crate::grammar::{format_http_date, parse_http_date};
+use crate::headers::{
+    EntityHeader, RequestHeader, TypedHeader, IF_MODIFIED_SINCE_HEADER_NAME,
+    IF_UNMODIFIED_SINCE_HEADER_NAME, LAST_MODIFIED_HEADER_NAME,
+};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// HTTP-dates have a resolution of one second, so times are truncated
+/// before they are compared with one
+pub(crate) fn truncate_to_seconds(time: SystemTime) -> SystemTime {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => UNIX_EPOCH + Duration::from_secs(d.as_secs()),
+        Err(_) => time,
+    }
+}
+
+/// The `Last-Modified` header, RFC 7232 section 2.2. A field-value which
+/// is not a valid HTTP-date is kept with no time rather than rejected.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LastModified {
+    time: Option<SystemTime>,
+    value: String,
+}
+
+impl LastModified {
+    /// Fractions of a second are dropped
+    pub fn new(time: SystemTime) -> LastModified {
+        let time = truncate_to_seconds(time);
+        LastModified {
+            time: Some(time),
+            value: format_http_date(time),
+        }
+    }
+
+    /// `None` if the field-value was not a valid HTTP-date
+    pub fn time(&self) -> Option<SystemTime> {
+        self.time
+    }
+}
+
+impl TypedHeader for LastModified {
+    const NAME: &'static str = LAST_MODIFIED_HEADER_NAME;
+
+    fn parse(value: &str) -> Result<Self, HttpErrors> {
+        let value = value.trim();
+        Ok(LastModified {
+            time: parse_http_date(value),
+            value: value.to_string(),
+        })
+    }
+
+    fn encode(&self) -> String {
+        self.value.clone()
+    }
+}
+
+impl EntityHeader for LastModified {}
+
+macro_rules! date_condition_header {
+    (
+        $(#[$docs:meta])*
+        $typ:ident, $name:expr
+    ) => {
+        $(#[$docs])*
+        ///
+        /// A field-value which is not a valid HTTP-date has to be ignored
+        /// rather than rejected, so it is kept with no time.
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct $typ {
+            time: Option<SystemTime>,
+            value: String,
+        }
+
+        impl $typ {
+            /// Fractions of a second are dropped
+            pub fn new(time: SystemTime) -> $typ {
+                let time = truncate_to_seconds(time);
+                $typ {
+                    time: Some(time),
+                    value: format_http_date(time),
+                }
+            }
+
+            /// `None` if the field-value was not a valid HTTP-date
+            pub fn time(&self) -> Option<SystemTime> {
+                self.time
+            }
+        }
+
+        impl TypedHeader for $typ {
+            const NAME: &'static str = $name;
+
+            fn parse(value: &str) -> Result<Self, HttpErrors> {
+                let value = value.trim();
+                Ok($typ {
+                    time: parse_http_date(value),
+                    value: value.to_string(),
+                })
+            }
+
+            fn encode(&self) -> String {
+                self.value.clone()
+            }
+        }
+
+        impl RequestHeader for $typ {}
+    };
+}
+
+date_condition_header! {
+    /// The `If-Modified-Since` header, RFC 7232 section 3.3
+    IfModifiedSince, IF_MODIFIED_SINCE_HEADER_NAME
+}
+
+date_condition_header! {
+    /// The `If-Unmodified-Since` header, RFC 7232 section 3.4
+    IfUnmodifiedSince, IF_UNMODIFIED_SINCE_HEADER_NAME
+}
+
+#[cfg(test)]
+mod tests_http_date {
+    use super::*;
+
+    #[test]
+    fn test_last_modified() {
+        let time = UNIX_EPOCH + Duration::from_millis(784111777_500);
+        let header = LastModified::new(time);
+        let expected = UNIX_EPOCH + Duration::from_secs(784111777);
+        assert_eq!(header.time(), Some(expected));
+        assert_eq!(header.encode(), "Sun, 06 Nov 1994 08:49:37 GMT");
+
+        let parsed = LastModified::parse("Sunday, 06-Nov-94 08:49:37 GMT");
+        assert_eq!(parsed.unwrap().time(), Some(expected));
+
+        let header = LastModified::parse("yesterday").unwrap();
+        assert_eq!(header.time(), None);
+        assert_eq!(header.encode(), "yesterday");
+    }
+
+    #[test]
+    fn test_date_conditions() {
+        let header =
+            IfModifiedSince::parse("Sun Nov  6 08:49:37 1994").unwrap();
+        let time = UNIX_EPOCH + Duration::from_secs(784111777);
+        assert_eq!(header.time(), Some(time));
+        assert_eq!(header.encode(), "Sun Nov  6 08:49:37 1994");
+        assert_eq!(
+            IfUnmodifiedSince::new(time).encode(),
+            "Sun, 06 Nov 1994 08:49:37 GMT"
+        );
+
+        let header = IfUnmodifiedSince::parse("yesterday").unwrap();
+        assert_eq!(header.time(), None);
+        assert_eq!(header.encode(), "yesterday");
+    }
+}
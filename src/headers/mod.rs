@@ -3,26 +3,70 @@ use crate::grammar::{
     is_cr, is_horizontal_tab, is_space, is_token, is_token_char,
     is_vchar_sequence_with_white_space, replace_white_space, to_lower_case,
 };
-use crate::headers::{ContentLength, ExtensionHeader};
+use crate::headers::ExtensionHeader;
 use paste::paste;
 use std::any::Any;
 use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fmt::Debug;
+use std::io;
 use std::str;
+use std::sync::RwLock;
 
 mod accept;
+mod accept_encoding;
+mod accept_language;
+mod cache_control;
 mod content_length;
+mod content_type;
+mod cookie;
+mod entity_tag;
 mod extension_header;
+mod http_date;
+mod parameters;
+mod preconditions;
+mod range;
 mod trailer;
 mod transfer_encoding;
 pub use accept::*;
+pub use accept_encoding::*;
+pub use accept_language::*;
+pub use cache_control::*;
 pub use content_length::*;
+pub use content_type::*;
+pub use cookie::*;
+pub use entity_tag::*;
 pub use extension_header::*;
+pub use http_date::*;
+pub use preconditions::*;
+pub use range::*;
 pub use trailer::*;
 pub use transfer_encoding::*;
 
-pub trait Header {
+/// A header field. Headers are stored in [`Headers`], which moves across
+/// threads together with the request, so they have to be thread-safe:
+///
+/// ```compile_fail
+/// use http11::headers::{Headers, TypedHeader};
+/// use std::rc::Rc;
+///
+/// struct NotThreadSafe(Rc<String>);
+///
+/// impl TypedHeader for NotThreadSafe {
+///     const NAME: &'static str = "x-not-thread-safe";
+///
+///     fn parse(value: &str) -> Result<Self, http11::errors::Error> {
+///         Ok(NotThreadSafe(Rc::new(value.to_string())))
+///     }
+///
+///     fn encode(&self) -> String {
+///         self.0.to_string()
+///     }
+/// }
+///
+/// Headers::new().insert_typed(NotThreadSafe(Rc::new("a".into())));
+/// ```
+pub trait Header: Send + Sync + 'static {
     fn name(&self) -> &str;
     fn value(&self) -> String;
     fn as_any(&self) -> &dyn Any;
@@ -31,6 +75,37 @@ pub trait Header {
     }
 }
 
+/// A header field with a typed representation. Every `TypedHeader` is also
+/// a [`Header`].
+///
+/// Received fields are only parsed into `T` if the type was registered with
+/// [`Headers::register`], otherwise they are kept as [`ExtensionHeader`].
+/// The typed headers of this crate are registered from the start.
+pub trait TypedHeader: Send + Sync + Sized + 'static {
+    /// Field name in lower case
+    const NAME: &'static str;
+
+    /// Parses the field-value
+    fn parse(value: &str) -> Result<Self, HttpError>;
+
+    /// Serializes the header into a field-value
+    fn encode(&self) -> String;
+}
+
+impl<T: TypedHeader> Header for T {
+    fn name(&self) -> &str {
+        T::NAME
+    }
+
+    fn value(&self) -> String {
+        self.encode()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
 pub trait GeneralHeader: Header {}
 
 pub trait RequestHeader: Header {}
@@ -42,9 +117,24 @@ pub trait EntityHeader: Header {}
 macro_rules! apply_header_names {
     ($macro_name:ident) => {
         $macro_name! {
-            "transfer-encoding";
-            "content-length";
-            "trailer";
+            "transfer-encoding" => TransferEncoding;
+            "content-length" => ContentLength;
+            "trailer" => Trailer;
+            "accept" => AcceptHeader;
+            "accept-encoding" => AcceptEncoding;
+            "accept-language" => AcceptLanguage;
+            "content-type" => ContentType;
+            "cookie" => Cookie;
+            "etag" => ETag;
+            "if-match" => IfMatch;
+            "if-none-match" => IfNoneMatch;
+            "if-modified-since" => IfModifiedSince;
+            "if-unmodified-since" => IfUnmodifiedSince;
+            "last-modified" => LastModified;
+            "range" => Range;
+            "content-range" => ContentRange;
+            "if-range" => IfRange;
+            "cache-control" => CacheControl;
         }
     };
 }
@@ -52,7 +142,7 @@ macro_rules! apply_header_names {
 macro_rules! header_names_constants {
     ($(
         $(#[$docs:meta])*
-        $name:expr;
+        $name:expr => $typ:ident;
     )*) => {
         $(
             paste! {
@@ -65,17 +155,13 @@ macro_rules! header_names_constants {
 macro_rules! get_header {
     ($(
         $(#[$docs:meta])*
-        $name:expr;
+        $name:expr => $typ:ident;
     )*) => {
         $(
             paste! {
                 $(#[$docs])*
-                pub fn [<$name:snake>](&self) -> Option<&[<$name:camel>]> {
-                    let h = self.headers.get([<$name:snake:upper _HEADER_NAME>]);
-                    match h {
-                        None => None,
-                        Some(x) => x.as_any().downcast_ref::<[<$name:camel>]>(),
-                    }
+                pub fn [<$name:snake>](&self) -> Option<&$typ> {
+                    self.get_typed::<$typ>()
                 }
             }
         )*
@@ -85,7 +171,7 @@ macro_rules! get_header {
 macro_rules! valid_headers {
     ($(
         $(#[$docs:meta])*
-        $name:expr;
+        $name:expr => $typ:ident;
     )*) => {
         fn is_valid_header_name(field_name: &str) -> bool {
             paste! {
@@ -100,42 +186,70 @@ macro_rules! valid_headers {
     };
 }
 
-macro_rules! get_header_struct {
+macro_rules! default_registry {
     ($(
         $(#[$docs:meta])*
-        $name:expr;
+        $name:expr => $typ:ident;
     )*) => {
-        paste! {
-            fn get_header_struct(name: &str, value: &str) -> Result<Option<Box<dyn Header>>, HttpError> {
-                if value == "" {
-                    return Ok(None);
-                }
-                let header: Box<dyn Header> = match name {
-                    $(
-                        [<$name:snake:upper _HEADER_NAME>] => {
-                            let header = [<$name:camel>]::try_from(value)?;
-                            Box::new(header)
-                        },
-                    )*
-                    _ => Box::new(ExtensionHeader::new(name, value)),
-                };
-                Ok(Some(header))
+        fn default_registry() -> HashMap<&'static str, ParseFn> {
+            let mut registry: HashMap<&'static str, ParseFn> = HashMap::new();
+            paste! {
+                $(
+                    registry.insert(
+                        [<$name:snake:upper _HEADER_NAME>],
+                        parse_boxed::<$typ>,
+                    );
+                )*
             }
+            registry
         }
     };
 }
 
 apply_header_names!(header_names_constants);
-const ACCEPT_HEADER_NAME: &str = "accept";
-const EXTENSION_HEADER_NAME: &str = "extension-header";
 
-#[derive(Debug)]
-pub struct Headers {
-    headers: HashMap<String, Box<dyn Header>>,
+type ParseFn = fn(&str) -> Result<Box<dyn Header>, HttpError>;
+
+fn parse_boxed<T: TypedHeader>(
+    value: &str,
+) -> Result<Box<dyn Header>, HttpError> {
+    Ok(Box::new(T::parse(value)?))
 }
 
-unsafe impl Send for Headers {}
-unsafe impl Sync for Headers {}
+/// Like `parse_boxed`, but keeps a value `T` cannot parse as it was
+/// received, where `get_typed::<T>` does not find it
+fn parse_or_extension<T: TypedHeader>(
+    value: &str,
+) -> Result<Box<dyn Header>, HttpError> {
+    match T::parse(value) {
+        Ok(header) => Ok(Box::new(header)),
+        Err(_) => Ok(Box::new(ExtensionHeader::new(T::NAME, value))),
+    }
+}
+
+apply_header_names!(default_registry);
+
+fn built_in_registry() -> HashMap<&'static str, ParseFn> {
+    let mut registry = default_registry();
+    // a request is served without its Content-Type, e.g. a bodyless GET
+    registry
+        .insert(CONTENT_TYPE_HEADER_NAME, parse_or_extension::<ContentType>);
+    registry
+}
+
+lazy_static! {
+    /// Parsers of the typed headers, by field name
+    static ref REGISTRY: RwLock<HashMap<&'static str, ParseFn>> =
+        RwLock::new(built_in_registry());
+}
+
+/// Header fields of a message in the order they were received. A field
+/// name may appear more than once, e.g. `Set-Cookie` or `Via`, and every
+/// occurrence is kept.
+#[derive(Debug, Default)]
+pub struct Headers {
+    headers: Vec<Box<dyn Header>>,
+}
 
 impl Debug for dyn Header {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
@@ -144,16 +258,184 @@ impl Debug for dyn Header {
 }
 
 impl Headers {
+    pub fn new() -> Self {
+        Headers { headers: vec![] }
+    }
+
+    /// First header field with the given name, which is matched
+    /// case-insensitively. Fields without a typed representation are
+    /// returned as [`ExtensionHeader`].
+    pub fn get(&self, name: &str) -> Option<&dyn Header> {
+        let name = name.to_lowercase();
+        self.headers
+            .iter()
+            .find(|h| h.name() == name)
+            .map(|h| h.as_ref())
+    }
+
+    /// All header fields with the given name, in the order they were received
+    pub fn get_all(&self, name: &str) -> Vec<&dyn Header> {
+        let name = name.to_lowercase();
+        self.headers
+            .iter()
+            .filter(|h| h.name() == name)
+            .map(|h| h.as_ref())
+            .collect()
+    }
+
+    /// Values of all fields with the given name joined by ", ", in order.
+    ///
+    /// RFC 7230 section 3.2.2 allows this only for fields defined as a
+    /// comma-separated list, like `Accept` or `Via`. `Set-Cookie` is the
+    /// common exception and has to be read with [`Headers::get_all`].
+    pub fn get_combined(&self, name: &str) -> Option<String> {
+        let values: Vec<String> =
+            self.get_all(name).iter().map(|h| h.value()).collect();
+
+        if values.is_empty() {
+            return None;
+        }
+
+        Some(values.join(", "))
+    }
+
+    /// All header fields in the order they were received
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Header> {
+        self.headers.iter().map(|h| h.as_ref())
+    }
+
+    /// Number of header fields, counting repeated fields separately
+    pub fn len(&self) -> usize {
+        self.headers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Sets the field `name` to `value`, replacing every field of the same
+    /// name. The new field takes the position of the first one replaced.
+    pub fn insert(&mut self, name: &str, value: &str) -> Result<(), HttpError> {
+        let header = Headers::new_header(name, value)?;
+        self.insert_header(header);
+        Ok(())
+    }
+
+    fn insert_header(&mut self, header: Box<dyn Header>) {
+        let name = header.name().to_string();
+        let position = self.headers.iter().position(|h| h.name() == name);
+
+        self.remove(&name);
+        match position {
+            None => self.headers.push(header),
+            Some(position) => self.headers.insert(position, header),
+        }
+    }
+
+    /// Adds the field after all existing ones, keeping earlier fields of the
+    /// same name
+    pub fn append(&mut self, name: &str, value: &str) -> Result<(), HttpError> {
+        let header = Headers::new_header(name, value)?;
+        self.headers.push(header);
+        Ok(())
+    }
+
+    /// Removes every field with the given name. Returns whether there was any.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        let len = self.headers.len();
+        self.headers.retain(|h| h.name() != name);
+        self.headers.len() != len
+    }
+
+    /// Writes the fields as `name: value` lines ending with CRLF, in order.
+    /// Each field is validated again, so a header cannot smuggle a line
+    /// break into the message.
+    pub fn write_to<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        for header in self.headers.iter() {
+            let value = header.value();
+            validate_field(header.name(), &value)?;
+            write!(writer, "{}: {}\r\n", header.name(), value)?;
+        }
+
+        Ok(())
+    }
+
+    fn new_header(
+        name: &str,
+        value: &str,
+    ) -> Result<Box<dyn Header>, HttpError> {
+        let name = name.to_lowercase();
+        validate_field(&name, value)?;
+        Headers::get_header_struct(&name, value)
+    }
+
+    /// Makes request parsing produce `T` for fields named `T::NAME`, so they
+    /// can be read with [`Headers::get_typed`]. Applies to every message
+    /// parsed afterwards, and replaces an earlier registration of the same
+    /// name, including the built-in ones.
+    ///
+    /// # Panics
+    ///
+    /// If `T::NAME` is `Content-Length` or `Transfer-Encoding`, as request
+    /// bodies are framed with the built-in types
+    pub fn register<T: TypedHeader>() {
+        assert!(
+            !is_framing_header(&T::NAME.to_lowercase()),
+            "the parser of the framing header {} cannot be replaced",
+            T::NAME
+        );
+
+        let mut registry = REGISTRY.write().unwrap();
+        registry.insert(T::NAME, parse_boxed::<T>);
+    }
+
+    /// First field named `T::NAME`, if it was parsed as `T`
+    pub fn get_typed<T: TypedHeader>(&self) -> Option<&T> {
+        self.get(T::NAME)
+            .and_then(|h| h.as_any().downcast_ref::<T>())
+    }
+
+    /// Like [`Headers::insert`] but with an already typed header
+    pub fn insert_typed<T: TypedHeader>(&mut self, header: T) {
+        self.insert_header(Box::new(header));
+    }
+
+    /// Like [`Headers::append`] but with an already typed header
+    pub fn append_typed<T: TypedHeader>(&mut self, header: T) {
+        self.headers.push(Box::new(header));
+    }
+
     apply_header_names!(get_header);
     apply_header_names!(valid_headers);
-    apply_header_names!(get_header_struct);
+
+    fn get_header_struct(
+        name: &str,
+        value: &str,
+    ) -> Result<Box<dyn Header>, HttpError> {
+        let parse = REGISTRY.read().unwrap().get(name).copied();
+        let header = match parse {
+            // an empty field is kept as it was received, but the message
+            // cannot be framed with an empty framing header
+            Some(parse) if !value.is_empty() || is_framing_header(name) => {
+                parse(value)?
+            }
+            _ => Box::new(ExtensionHeader::new(name, value)),
+        };
+
+        Ok(header)
+    }
 }
 
 impl<'a> TryFrom<String> for Headers {
     type Error = HttpError;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        let mut headers = HashMap::new();
+        let mut headers = vec![];
 
         let parts = value.split("\r\n").filter(|p| p != &"");
 
@@ -180,30 +462,36 @@ impl<'a> TryFrom<String> for Headers {
             let value = replace_white_space(value.trim());
             let value = value.as_str();
 
-            if !is_token(name.as_bytes()) {
-                return Err(HttpError::InvalidHeaderField(format!(
-                    "The header field-name has invalid character:- {}",
-                    name
-                )));
-            }
-
-            if !is_vchar_sequence_with_white_space(value.as_bytes()) {
-                return Err(HttpError::InvalidHeaderFieldValue(format!(
-                    "{}",
-                    value
-                )));
-            }
-
+            validate_field(&name, value)?;
             let header = Headers::get_header_struct(name.as_str(), value)?;
-            if let Some(header) = header {
-                headers.insert(header.name().to_string(), header);
-            }
+            headers.push(header);
         }
 
         Ok(Headers { headers })
     }
 }
 
+/// Whether the field frames the message body, which a request cannot be
+/// read without
+fn is_framing_header(name: &str) -> bool {
+    name == CONTENT_LENGTH_HEADER_NAME || name == TRANSFER_ENCODING_HEADER_NAME
+}
+
+fn validate_field(name: &str, value: &str) -> Result<(), HttpError> {
+    if name.is_empty() || !is_token(name.as_bytes()) {
+        return Err(HttpError::InvalidHeaderField(format!(
+            "The header field-name has invalid character:- {}",
+            name
+        )));
+    }
+
+    if !is_vchar_sequence_with_white_space(value.as_bytes()) {
+        return Err(HttpError::InvalidHeaderFieldValue(value.to_string()));
+    }
+
+    Ok(())
+}
+
 fn is_continued_field(field: &str) -> bool {
     let space_index = field.find(" ");
     let tab_index = field.find("\t");
@@ -217,8 +505,9 @@ fn is_continued_field(field: &str) -> bool {
 
 #[cfg(test)]
 mod tests_header {
-    use super::Headers;
+    use super::{Header, Headers, TypedHeader};
     use crate::errors::Error;
+    use crate::headers::{ContentLength, Trailer, TransferEncoding};
     use crate::{assert_match, assert_match_error};
     use std::convert::TryFrom;
     use std::str;
@@ -287,9 +576,276 @@ mod tests_header {
         assert_match_error!(result, expected_error);
     }
 
+    #[test]
+    fn test_extension_headers_keep_their_names() {
+        let buffer = "User-Agent: curl/7.68.0\r\nX-Request-Id: 42\r\n\
+                      Authorization: Basic YWJj\r\nContent-Length: 3";
+        let result = Headers::try_from(buffer.to_string()).unwrap();
+        assert_eq!(result.headers.len(), 4);
+
+        let user_agent = result.get("user-agent").unwrap();
+        assert_eq!(user_agent.name(), "user-agent");
+        assert_eq!(user_agent.value(), "curl/7.68.0");
+
+        assert_eq!(result.get("X-Request-ID").unwrap().value(), "42");
+        assert_eq!(result.get("authorization").unwrap().value(), "Basic YWJj");
+        assert_eq!(result.get("content-length").unwrap().value(), "3");
+        assert!(result.get("accept").is_none());
+    }
+
+    #[test]
+    fn test_repeated_fields() {
+        let buffer =
+            "Via: 1.0 fred\r\nSet-Cookie: a=1\r\nAccept: text/html\r\n\
+                      Via: 1.1 p.example.net\r\nset-cookie: b=2\r\n\
+                      ACCEPT: application/json;q=0.9";
+        let result = Headers::try_from(buffer.to_string()).unwrap();
+        assert_eq!(result.len(), 6);
+
+        assert_eq!(result.get("via").unwrap().value(), "1.0 fred");
+        let cookies: Vec<String> = result
+            .get_all("Set-Cookie")
+            .iter()
+            .map(|h| h.value())
+            .collect();
+        assert_eq!(cookies, vec!["a=1", "b=2"]);
+        assert!(result.get_all("x-missing").is_empty());
+
+        assert_eq!(
+            result.get_combined("via").unwrap(),
+            "1.0 fred, 1.1 p.example.net"
+        );
+        assert_eq!(
+            result.get_combined("accept").unwrap(),
+            "text/html, application/json;q=0.9"
+        );
+        assert!(result.get_combined("x-missing").is_none());
+    }
+
+    #[test]
+    fn test_iterates_in_wire_order() {
+        let buffer = "Host: example.com\r\nX-B: 1\r\nContent-Length: 0\r\n\
+                      X-A: 2\r\nX-B: 3";
+        let result = Headers::try_from(buffer.to_string()).unwrap();
+        let fields: Vec<String> =
+            result.iter().map(|h| h.header_string()).collect();
+
+        assert_eq!(
+            fields,
+            vec![
+                "host: example.com",
+                "x-b: 1",
+                "content-length: 0",
+                "x-a: 2",
+                "x-b: 3"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keeps_empty_fields() {
+        let buffer = "X-Empty:\r\nAccept: \r\nX-B: 1\r\nX-Empty: 2";
+        let result = Headers::try_from(buffer.to_string()).unwrap();
+        let fields: Vec<String> =
+            result.iter().map(|h| h.header_string()).collect();
+        assert_eq!(
+            fields,
+            vec!["x-empty: ", "accept: ", "x-b: 1", "x-empty: 2"]
+        );
+        assert_eq!(result.get("x-empty").unwrap().value(), "");
+
+        // framing headers are parsed even when empty
+        assert!(Headers::try_from("Content-Length:".to_string()).is_err());
+        let result = Headers::try_from("Transfer-Encoding:".to_string());
+        assert!(result.unwrap().transfer_encoding().is_some());
+    }
+
+    #[test]
+    fn test_insert_and_append() {
+        let mut headers = Headers::new();
+        assert!(headers.is_empty());
+
+        headers.insert("Content-Type", "text/plain").unwrap();
+        headers.append("Set-Cookie", "a=1").unwrap();
+        headers.append("set-cookie", "b=2").unwrap();
+        headers.insert("Content-Length", "12").unwrap();
+        assert_eq!(headers.len(), 4);
+        assert!(headers.contains("set-cookie"));
+        assert_eq!(headers.content_length().unwrap().len(), 12);
+
+        headers.insert("SET-COOKIE", "c=3").unwrap();
+        let fields: Vec<String> =
+            headers.iter().map(|h| h.header_string()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "content-type: text/plain",
+                "set-cookie: c=3",
+                "content-length: 12"
+            ]
+        );
+
+        headers.insert("content-type", "").unwrap();
+        assert_eq!(headers.get("content-type").unwrap().value(), "");
+    }
+
+    #[test]
+    fn test_remove() {
+        let buffer = "Via: a\r\nHost: example.com\r\nVia: b";
+        let mut headers = Headers::try_from(buffer.to_string()).unwrap();
+
+        assert!(headers.remove("VIA"));
+        assert!(!headers.contains("via"));
+        assert!(!headers.remove("via"));
+        assert_eq!(headers.len(), 1);
+    }
+
+    #[test]
+    fn test_rejects_invalid_fields() {
+        let mut headers = Headers::new();
+        let result = headers.insert("x-injected", "a\r\nSet-Cookie: evil=1");
+        let expected_error =
+            Error::InvalidHeaderFieldValue("a\r\nSet-Cookie: evil=1".into());
+        assert_match_error!(result.err().unwrap(), expected_error);
+
+        let result = headers.append("bad name", "value");
+        let expected_error = Error::InvalidHeaderField(
+            "The header field-name has invalid character:- bad name".into(),
+        );
+        assert_match_error!(result.err().unwrap(), expected_error);
+
+        assert!(headers.append("", "value").is_err());
+        assert!(headers.append("x-nul", "a\0b").is_err());
+        assert!(headers.insert("content-length", "abc").is_err());
+        assert!(headers.is_empty());
+    }
+
+    #[test]
+    fn test_write_to() {
+        let mut headers = Headers::new();
+        headers.append("Content-Length", "5").unwrap();
+        headers.append("X-Trace", "1").unwrap();
+        headers.append("X-Trace", "2").unwrap();
+
+        let mut buffer = vec![];
+        headers.write_to(&mut buffer).unwrap();
+        assert_eq!(
+            str::from_utf8(&buffer).unwrap(),
+            "content-length: 5\r\nx-trace: 1\r\nx-trace: 2\r\n"
+        );
+
+        let mut buffer = vec![];
+        Headers::new().write_to(&mut buffer).unwrap();
+        assert!(buffer.is_empty());
+    }
+
+    struct ApiVersion {
+        major: u32,
+        minor: u32,
+    }
+
+    impl TypedHeader for ApiVersion {
+        const NAME: &'static str = "x-api-version";
+
+        fn parse(value: &str) -> Result<Self, Error> {
+            let invalid = || Error::InvalidHeaderFieldValue(value.to_string());
+            let (major, minor) = value.split_once('.').ok_or_else(invalid)?;
+
+            Ok(ApiVersion {
+                major: major.parse().map_err(|_| invalid())?,
+                minor: minor.parse().map_err(|_| invalid())?,
+            })
+        }
+
+        fn encode(&self) -> String {
+            format!("{}.{}", self.major, self.minor)
+        }
+    }
+
+    #[test]
+    fn test_built_in_typed_headers() {
+        let buffer = "Content-Length: 42\r\nTransfer-Encoding: gzip, chunked";
+        let headers = Headers::try_from(buffer.to_string()).unwrap();
+
+        let content_length = headers.get_typed::<ContentLength>().unwrap();
+        assert_eq!(content_length.len(), 42);
+        assert!(headers
+            .get_typed::<TransferEncoding>()
+            .unwrap()
+            .is_chunked());
+        assert!(headers.get_typed::<Trailer>().is_none());
+        assert!(headers.get_typed::<ApiVersion>().is_none());
+    }
+
+    #[test]
+    fn test_application_typed_header() {
+        Headers::register::<ApiVersion>();
+
+        let buffer = "X-Api-Version: 2.7";
+        let headers = Headers::try_from(buffer.to_string()).unwrap();
+        let version = headers.get_typed::<ApiVersion>().unwrap();
+        assert_eq!((version.major, version.minor), (2, 7));
+        assert_eq!(headers.get("x-api-version").unwrap().value(), "2.7");
+
+        let buffer = "X-Api-Version: two";
+        let result = Headers::try_from(buffer.to_string());
+        let expected_error = Error::InvalidHeaderFieldValue("two".into());
+        assert_match_error!(result.err().unwrap(), expected_error);
+    }
+
+    struct Length(usize);
+
+    impl TypedHeader for Length {
+        const NAME: &'static str = "Content-Length";
+
+        fn parse(value: &str) -> Result<Self, Error> {
+            Ok(Length(value.parse().unwrap_or_default()))
+        }
+
+        fn encode(&self) -> String {
+            self.0.to_string()
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "framing header Content-Length")]
+    fn test_framing_headers_cannot_be_registered() {
+        Headers::register::<Length>();
+    }
+
+    #[test]
+    fn test_insert_typed() {
+        let mut headers = Headers::new();
+        headers.insert("X-Api-Version", "1.0").unwrap();
+        headers.append("Via", "1.1 proxy").unwrap();
+        headers.insert_typed(ApiVersion { major: 3, minor: 1 });
+        headers.append_typed(ContentLength::try_from("0").unwrap());
+
+        assert_eq!(headers.get_typed::<ApiVersion>().unwrap().major, 3);
+        assert_eq!(headers.get_all("x-api-version").len(), 1);
+
+        let mut buffer = vec![];
+        headers.write_to(&mut buffer).unwrap();
+        assert_eq!(
+            str::from_utf8(&buffer).unwrap(),
+            "x-api-version: 3.1\r\nvia: 1.1 proxy\r\ncontent-length: 0\r\n"
+        );
+    }
+
+    #[test]
+    fn test_headers_are_thread_safe() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Headers>();
+        assert_send_sync::<Box<dyn Header>>();
+
+        let headers = Headers::try_from("X-Id: 1".to_string()).unwrap();
+        let handle = std::thread::spawn(move || headers.get("x-id").is_some());
+        assert!(handle.join().unwrap());
+    }
+
     #[test]
     fn test_valid_char_in_value() {
-        let mut buffer = "accept: ab cd\t".to_string();
+        let mut buffer = "x-value: ab cd\t".to_string();
         for i in 33..=126 {
             buffer += str::from_utf8(&[i]).unwrap();
         }
@@ -0,0 +1,246 @@
+use crate::errors::Error as HttpError;
+use crate::grammar::is_token;
+
+/// Splits a field-value at every `delimiter` which is not inside a
+/// quoted-string. Elements are trimmed and empty ones are dropped, as
+/// RFC 7230 section 7 asks recipients of lists to do.
+pub(crate) fn split_quoted(value: &str, delimiter: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut in_quotes = false;
+    let mut escaped = false;
+
+    for (i, c) in value.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+
+        match c {
+            '\\' if in_quotes => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            c if c == delimiter && !in_quotes => {
+                parts.push(&value[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => (),
+        }
+    }
+
+    parts.push(&value[start..]);
+    parts
+        .into_iter()
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+/// Parses `name=value` where the value is a token or a quoted-string.
+/// The name is lower-cased and the value unquoted.
+pub(crate) fn parse_parameter(
+    parameter: &str,
+) -> Result<(String, String), HttpError> {
+    let invalid = || HttpError::InvalidHeaderFieldValue(parameter.to_string());
+
+    let (name, value) = parameter.split_once('=').ok_or_else(invalid)?;
+    let (name, value) = (name.trim(), value.trim());
+    if name.is_empty() || !is_token(name.as_bytes()) {
+        return Err(invalid());
+    }
+
+    let value = if value.starts_with('"') {
+        unquote(value).ok_or_else(invalid)?
+    } else if !value.is_empty() && is_token(value.as_bytes()) {
+        value.to_string()
+    } else {
+        return Err(invalid());
+    };
+
+    Ok((name.to_ascii_lowercase(), value))
+}
+
+/// Content of a quoted-string with quoted-pairs resolved, `None` if `value`
+/// is not exactly one quoted-string
+pub(crate) fn unquote(value: &str) -> Option<String> {
+    let inner = value.strip_prefix('"')?.strip_suffix('"')?;
+    let mut result = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => result.push(chars.next()?),
+            '"' => return None,
+            c => result.push(c),
+        }
+    }
+
+    Some(result)
+}
+
+/// `value` as a token if possible, otherwise as a quoted-string
+pub(crate) fn quote(value: &str) -> String {
+    if !value.is_empty() && is_token(value.as_bytes()) {
+        return value.to_string();
+    }
+
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+    for c in value.chars() {
+        if c == '"' || c == '\\' {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result.push('"');
+
+    result
+}
+
+/// Parses a weight, RFC 7231 section 5.3.1, into thousandths
+///
+/// qvalue = ( "0" [ "." 0*3DIGIT ] ) / ( "1" [ "." 0*3("0") ] )
+pub(crate) fn parse_quality(value: &str) -> Result<u16, HttpError> {
+    let invalid = || HttpError::InvalidHeaderFieldValue(value.to_string());
+
+    let (integer, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if fraction.len() > 3 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+
+    let mut quality = match integer {
+        "0" => 0,
+        "1" => 1000,
+        _ => return Err(invalid()),
+    };
+
+    let fraction = format!("{:0<3}", fraction);
+    let fraction: u16 = fraction.parse().map_err(|_| invalid())?;
+    if quality == 1000 && fraction != 0 {
+        return Err(invalid());
+    }
+
+    quality += fraction;
+    Ok(quality)
+}
+
+/// Parses a list of `item [;q=weight]` elements as used by
+/// `Accept-Encoding` and `Accept-Language`. Items are lower-cased, the
+/// weights are in thousandths. Elements with a malformed weight or an item
+/// which is not `is_item` are left out.
+pub(crate) fn parse_weighted_list(
+    value: &str,
+    is_item: impl Fn(&str) -> bool,
+) -> Vec<(String, u16)> {
+    split_quoted(value, ',')
+        .into_iter()
+        .filter_map(|element| parse_weighted(element).ok())
+        .filter(|(item, _)| is_item(item))
+        .collect()
+}
+
+/// Parses one `item [;q=weight]` element of a weighted list
+pub(crate) fn parse_weighted(
+    element: &str,
+) -> Result<(String, u16), HttpError> {
+    let mut parts = split_quoted(element, ';').into_iter();
+    let item = parts.next().unwrap_or_default();
+    let mut quality = 1000;
+
+    for parameter in parts {
+        let (name, value) = parse_parameter(parameter)?;
+        if name == "q" {
+            quality = parse_quality(&value)?;
+        }
+    }
+
+    Ok((item.to_ascii_lowercase(), quality))
+}
+
+/// `item;q=weight` with the weight left out when it is 1
+pub(crate) fn format_weighted(item: &str, quality: u16) -> String {
+    match quality {
+        1000 => item.to_string(),
+        q => format!("{};q={}", item, format_quality(q)),
+    }
+}
+
+/// Shortest qvalue for a weight in thousandths
+pub(crate) fn format_quality(quality: u16) -> String {
+    match quality {
+        1000 => "1".to_string(),
+        0 => "0".to_string(),
+        q => format!("0.{:03}", q).trim_end_matches('0').to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests_parameters {
+    use super::*;
+
+    #[test]
+    fn test_split_quoted() {
+        let parts = split_quoted(r#"a, b;x="1,2" , ,c;y="\",", d"#, ',');
+        assert_eq!(parts, vec!["a", r#"b;x="1,2""#, r#"c;y="\",""#, "d"]);
+        assert!(split_quoted(" , ", ',').is_empty());
+    }
+
+    #[test]
+    fn test_parse_parameter() {
+        let parameter = parse_parameter("Charset=UTF-8").unwrap();
+        assert_eq!(parameter, ("charset".to_string(), "UTF-8".to_string()));
+
+        let parameter = parse_parameter(r#"title="a \"b\"; c""#).unwrap();
+        assert_eq!(parameter.1, r#"a "b"; c"#);
+
+        for invalid in
+            ["a", "=b", "a=", "a=b c", r#"a="b"c""#, r#"a="b"#].iter()
+        {
+            assert!(parse_parameter(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("utf-8"), "utf-8");
+        assert_eq!(quote(""), r#""""#);
+        assert_eq!(quote(r#"a "b"\c"#), r#""a \"b\"\\c""#);
+        assert_eq!(unquote(&quote(r#"a "b"\c"#)).unwrap(), r#"a "b"\c"#);
+    }
+
+    #[test]
+    fn test_quality() {
+        assert_eq!(parse_quality("1").unwrap(), 1000);
+        assert_eq!(parse_quality("1.000").unwrap(), 1000);
+        assert_eq!(parse_quality("0").unwrap(), 0);
+        assert_eq!(parse_quality("0.5").unwrap(), 500);
+        assert_eq!(parse_quality("0.25").unwrap(), 250);
+        assert_eq!(parse_quality("0.001").unwrap(), 1);
+        assert_eq!(parse_quality("0.").unwrap(), 0);
+
+        for invalid in ["", "2", "1.1", "0.1234", "-0", "0.a", ".5"].iter() {
+            assert!(parse_quality(invalid).is_err(), "{}", invalid);
+        }
+
+        assert_eq!(format_quality(1000), "1");
+        assert_eq!(format_quality(500), "0.5");
+        assert_eq!(format_quality(25), "0.025");
+        assert_eq!(format_quality(0), "0");
+    }
+
+    #[test]
+    fn test_weighted_list() {
+        let is_item = |item: &str| !item.is_empty();
+        let list = parse_weighted_list("GZip;q=0.5, br, *;q=0", is_item);
+        let expected = vec![
+            ("gzip".to_string(), 500),
+            ("br".to_string(), 1000),
+            ("*".to_string(), 0),
+        ];
+        assert_eq!(list, expected);
+        let list = parse_weighted_list("gzip;q=x, br, x;q=1.5", is_item);
+        assert_eq!(list, vec![("br".to_string(), 1000)]);
+
+        assert_eq!(format_weighted("gzip", 500), "gzip;q=0.5");
+        assert_eq!(format_weighted("br", 1000), "br");
+    }
+}
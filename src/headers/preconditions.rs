@@ -0,0 +1,269 @@
+use crate::headers::http_date::truncate_to_seconds;
+use crate::headers::{
+    ETag, EntityTag, Headers, IfMatch, IfNoneMatch, LastModified, TypedHeader,
+};
+use crate::request::HttpMethods;
+use crate::status::StatusCode;
+use std::time::SystemTime;
+
+/// The validators of the selected representation of a resource, against
+/// which the conditional request headers are evaluated
+#[derive(Clone, Debug, Default)]
+pub struct Validators {
+    etag: Option<EntityTag>,
+    last_modified: Option<SystemTime>,
+}
+
+impl Validators {
+    pub fn new() -> Validators {
+        Validators {
+            etag: None,
+            last_modified: None,
+        }
+    }
+
+    pub fn with_etag(mut self, etag: EntityTag) -> Validators {
+        self.etag = Some(etag);
+        self
+    }
+
+    /// Fractions of a second are dropped, as HTTP-dates have none
+    pub fn with_last_modified(mut self, time: SystemTime) -> Validators {
+        self.last_modified = Some(truncate_to_seconds(time));
+        self
+    }
+
+    pub fn etag(&self) -> Option<&EntityTag> {
+        self.etag.as_ref()
+    }
+
+    pub fn last_modified(&self) -> Option<SystemTime> {
+        self.last_modified
+    }
+
+    /// Evaluates the preconditions of a request in the order of RFC 7232
+    /// section 6, for a resource which has a current representation.
+    /// Invalid conditions are ignored, as if the field was not sent.
+    ///
+    /// Returns `304 Not Modified` or `412 Precondition Failed` if the
+    /// request must not be performed, `None` if it may.
+    pub fn evaluate(
+        &self,
+        method: &HttpMethods,
+        headers: &Headers,
+    ) -> Option<StatusCode> {
+        let is_get_or_head =
+            matches!(method, HttpMethods::GET | HttpMethods::HEAD);
+
+        let mut if_match = all_typed::<IfMatch>(headers);
+        if_match.retain(|h| !matches!(h, IfMatch::Invalid(_)));
+        if !if_match.is_empty() {
+            if !if_match.iter().any(|h| h.matches(self.etag())) {
+                return Some(StatusCode::PRECONDITION_FAILED);
+            }
+        } else if let Some(since) = headers.if_unmodified_since() {
+            if self.is_modified_since(since.time()) == Some(true) {
+                return Some(StatusCode::PRECONDITION_FAILED);
+            }
+        }
+
+        let mut if_none_match = all_typed::<IfNoneMatch>(headers);
+        if_none_match.retain(|h| !matches!(h, IfNoneMatch::Invalid(_)));
+        if !if_none_match.is_empty() {
+            if if_none_match.iter().any(|h| h.matches(self.etag())) {
+                return Some(if is_get_or_head {
+                    StatusCode::NOT_MODIFIED
+                } else {
+                    StatusCode::PRECONDITION_FAILED
+                });
+            }
+        } else if is_get_or_head {
+            if let Some(since) = headers.if_modified_since() {
+                if self.is_modified_since(since.time()) == Some(false) {
+                    return Some(StatusCode::NOT_MODIFIED);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// `None` if either the date or the modification date is unknown, in
+    /// which case the condition is ignored
+    fn is_modified_since(&self, date: Option<SystemTime>) -> Option<bool> {
+        Some(self.last_modified? > date?)
+    }
+
+    /// Sets the `ETag` and `Last-Modified` headers a response, including a
+    /// `304 Not Modified` one, has to carry
+    pub fn apply_to(&self, headers: &mut Headers) {
+        if let Some(etag) = &self.etag {
+            headers.insert_typed(ETag::new(etag.clone()));
+        }
+
+        if let Some(time) = self.last_modified {
+            headers.insert_typed(LastModified::new(time));
+        }
+    }
+}
+
+/// Every field of a list header whose values may be split over several
+/// field lines
+fn all_typed<T: TypedHeader>(headers: &Headers) -> Vec<&T> {
+    headers
+        .get_all(T::NAME)
+        .into_iter()
+        .filter_map(|h| h.as_any().downcast_ref::<T>())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests_preconditions {
+    use super::*;
+    use crate::request::RequestBuilder;
+    use std::convert::TryFrom;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    const DATE: &str = "Sun, 06 Nov 1994 08:49:37 GMT";
+
+    fn validators() -> Validators {
+        let time = UNIX_EPOCH + Duration::from_millis(784111777_500);
+        Validators::new()
+            .with_etag(EntityTag::strong("v1").unwrap())
+            .with_last_modified(time)
+    }
+
+    fn evaluate(method: HttpMethods, headers: &str) -> Option<StatusCode> {
+        let headers = Headers::try_from(headers.to_string()).unwrap();
+        validators().evaluate(&method, &headers)
+    }
+
+    #[test]
+    fn test_if_match() {
+        assert_eq!(evaluate(HttpMethods::PUT, "If-Match: \"v1\""), None);
+        assert_eq!(evaluate(HttpMethods::PUT, "If-Match: *"), None);
+        assert_eq!(
+            evaluate(HttpMethods::PUT, "If-Match: \"v0\"\r\nIf-Match: \"v1\""),
+            None
+        );
+        assert_eq!(
+            evaluate(HttpMethods::PUT, "If-Match: W/\"v1\""),
+            Some(StatusCode::PRECONDITION_FAILED)
+        );
+
+        let headers = Headers::try_from("If-Match: *".to_string()).unwrap();
+        let result = Validators::new().evaluate(&HttpMethods::PUT, &headers);
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn test_if_unmodified_since() {
+        let headers = format!("If-Unmodified-Since: {}", DATE);
+        assert_eq!(evaluate(HttpMethods::PUT, &headers), None);
+
+        let headers = "If-Unmodified-Since: Sun, 06 Nov 1994 08:49:36 GMT";
+        assert_eq!(
+            evaluate(HttpMethods::PUT, headers),
+            Some(StatusCode::PRECONDITION_FAILED)
+        );
+
+        // If-Match takes precedence and invalid dates are ignored
+        let headers = format!("{}\r\nIf-Match: \"v1\"", headers);
+        assert_eq!(evaluate(HttpMethods::PUT, &headers), None);
+        let headers = "If-Unmodified-Since: yesterday";
+        assert_eq!(evaluate(HttpMethods::PUT, headers), None);
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        assert_eq!(
+            evaluate(HttpMethods::GET, "If-None-Match: W/\"v1\""),
+            Some(StatusCode::NOT_MODIFIED)
+        );
+        assert_eq!(
+            evaluate(HttpMethods::HEAD, "If-None-Match: \"v0\", \"v1\""),
+            Some(StatusCode::NOT_MODIFIED)
+        );
+        assert_eq!(
+            evaluate(HttpMethods::PUT, "If-None-Match: *"),
+            Some(StatusCode::PRECONDITION_FAILED)
+        );
+        assert_eq!(evaluate(HttpMethods::GET, "If-None-Match: \"v0\""), None);
+
+        // If-None-Match takes precedence over If-Modified-Since
+        let headers =
+            format!("If-None-Match: \"v0\"\r\nIf-Modified-Since: {}", DATE);
+        assert_eq!(evaluate(HttpMethods::GET, &headers), None);
+    }
+
+    #[test]
+    fn test_if_modified_since() {
+        let headers = format!("If-Modified-Since: {}", DATE);
+        assert_eq!(
+            evaluate(HttpMethods::GET, &headers),
+            Some(StatusCode::NOT_MODIFIED)
+        );
+        assert_eq!(evaluate(HttpMethods::POST, &headers), None);
+
+        let headers = "If-Modified-Since: Sun, 06 Nov 1994 08:49:36 GMT";
+        assert_eq!(evaluate(HttpMethods::GET, headers), None);
+        assert_eq!(
+            evaluate(HttpMethods::GET, "If-Modified-Since: yesterday"),
+            None
+        );
+
+        let headers = Headers::try_from(format!("If-Modified-Since: {}", DATE));
+        let result = Validators::new()
+            .with_etag(EntityTag::strong("v1").unwrap())
+            .evaluate(&HttpMethods::GET, &headers.unwrap());
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn test_invalid_conditions_are_ignored() {
+        assert_eq!(evaluate(HttpMethods::PUT, "If-Match: abc"), None);
+        assert_eq!(
+            evaluate(HttpMethods::PUT, "If-Match: abc\r\nIf-Match: \"v0\""),
+            Some(StatusCode::PRECONDITION_FAILED)
+        );
+
+        // If-Modified-Since applies as if If-None-Match was not sent
+        let headers =
+            format!("If-None-Match: \"a\", W/b\r\nIf-Modified-Since: {}", DATE);
+        assert_eq!(
+            evaluate(HttpMethods::GET, &headers),
+            Some(StatusCode::NOT_MODIFIED)
+        );
+        assert_eq!(evaluate(HttpMethods::GET, "If-None-Match: abc"), None);
+    }
+
+    #[test]
+    fn test_request_with_invalid_validators() {
+        let buffer = "GET / HTTP/1.1\r\nIf-None-Match: abc\r\n\
+                      If-None-Match: \"a\", W/b\r\nIf-Match: abc\r\n\
+                      ETag: abc\r\nLast-Modified: garbage\r\n\r\n";
+        let mut builder = RequestBuilder::new();
+        builder
+            .parse(buffer.as_bytes().to_vec(), buffer.len())
+            .unwrap();
+        let request = builder.build();
+
+        let headers = request.headers();
+        assert!(headers.etag().unwrap().entity_tag().is_none());
+        assert!(headers.last_modified().unwrap().time().is_none());
+        assert_eq!(validators().evaluate(request.method(), headers), None);
+    }
+
+    #[test]
+    fn test_apply_to() {
+        let mut headers = Headers::new();
+        validators().apply_to(&mut headers);
+
+        let mut buffer = vec![];
+        headers.write_to(&mut buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            format!("etag: \"v1\"\r\nlast-modified: {}\r\n", DATE)
+        );
+    }
+}
@@ -0,0 +1,400 @@
+use crate::errors::Error as HttpErrors;
+use crate::grammar::{format_http_date, parse_http_date};
+use crate::headers::{
+    EntityTag, RequestHeader, ResponseHeader, TypedHeader, Validators,
+    CONTENT_RANGE_HEADER_NAME, IF_RANGE_HEADER_NAME, RANGE_HEADER_NAME,
+};
+use std::convert::TryFrom;
+use std::fmt;
+use std::time::SystemTime;
+
+/// A byte-range-spec or suffix-byte-range-spec, RFC 7233 section 2.1
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ByteRange {
+    /// `first-last`, both inclusive
+    FromTo(u64, u64),
+    /// `first-`, up to the end
+    From(u64),
+    /// `-length`, the last `length` bytes
+    Last(u64),
+}
+
+impl ByteRange {
+    /// First and last byte, both inclusive, of the range within a
+    /// representation of `len` bytes. `None` if the range is not
+    /// satisfiable.
+    pub fn bounds(&self, len: u64) -> Option<(u64, u64)> {
+        match *self {
+            ByteRange::FromTo(first, _) | ByteRange::From(first)
+                if first >= len =>
+            {
+                None
+            }
+            ByteRange::FromTo(first, last) => Some((first, last.min(len - 1))),
+            ByteRange::From(first) => Some((first, len - 1)),
+            ByteRange::Last(0) => None,
+            ByteRange::Last(_) if len == 0 => None,
+            ByteRange::Last(length) => Some((len - length.min(len), len - 1)),
+        }
+    }
+}
+
+impl TryFrom<&str> for ByteRange {
+    type Error = HttpErrors;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let invalid = || HttpErrors::InvalidRange(value.to_string());
+        let (first, last) = value.trim().split_once('-').ok_or_else(invalid)?;
+
+        let range = match (parse_position(first), parse_position(last)) {
+            (Some(first), Some(last)) if first <= last => {
+                ByteRange::FromTo(first, last)
+            }
+            (Some(first), None) if last.is_empty() => ByteRange::From(first),
+            (None, Some(length)) if first.is_empty() => ByteRange::Last(length),
+            _ => return Err(invalid()),
+        };
+
+        Ok(range)
+    }
+}
+
+impl fmt::Display for ByteRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ByteRange::FromTo(first, last) => write!(f, "{}-{}", first, last),
+            ByteRange::From(first) => write!(f, "{}-", first),
+            ByteRange::Last(length) => write!(f, "-{}", length),
+        }
+    }
+}
+
+fn parse_position(value: &str) -> Option<u64> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    value.parse().ok()
+}
+
+/// The `Range` header, RFC 7233 section 3.1, like `bytes=0-499, -500`.
+///
+/// A request must not fail because of its `Range` header. Ranges in other
+/// units than `bytes` and invalid byte range sets like `bytes=5-1` have to
+/// be ignored, so they are kept as `Unsupported` and `Invalid`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Range {
+    Bytes(Vec<ByteRange>),
+    Unsupported(String),
+    Invalid(String),
+}
+
+impl Range {
+    pub fn bytes(ranges: Vec<ByteRange>) -> Range {
+        Range::Bytes(ranges)
+    }
+
+    /// Bounds of the satisfiable ranges for a representation of `len`
+    /// bytes, in the requested order. Empty if none is satisfiable, the
+    /// unit is not supported or the ranges are invalid.
+    pub fn satisfiable(&self, len: u64) -> Vec<(u64, u64)> {
+        match self {
+            Range::Bytes(ranges) => {
+                ranges.iter().filter_map(|r| r.bounds(len)).collect()
+            }
+            Range::Unsupported(_) | Range::Invalid(_) => vec![],
+        }
+    }
+}
+
+impl TryFrom<&str> for Range {
+    type Error = HttpErrors;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value = value.trim();
+        let ranges = match value.split_once('=') {
+            Some((unit, ranges)) if unit.eq_ignore_ascii_case("bytes") => {
+                ranges
+            }
+            _ => return Ok(Range::Unsupported(value.to_string())),
+        };
+
+        let ranges = ranges
+            .split(',')
+            .map(str::trim)
+            .filter(|r| !r.is_empty())
+            .map(ByteRange::try_from)
+            .collect::<Result<Vec<ByteRange>, HttpErrors>>();
+
+        match ranges {
+            Ok(ranges) if !ranges.is_empty() => Ok(Range::Bytes(ranges)),
+            _ => Ok(Range::Invalid(value.to_string())),
+        }
+    }
+}
+
+impl TypedHeader for Range {
+    const NAME: &'static str = RANGE_HEADER_NAME;
+
+    fn parse(value: &str) -> Result<Self, HttpErrors> {
+        Range::try_from(value)
+    }
+
+    fn encode(&self) -> String {
+        match self {
+            Range::Bytes(ranges) => {
+                let ranges: Vec<String> =
+                    ranges.iter().map(|r| r.to_string()).collect();
+                format!("bytes={}", ranges.join(", "))
+            }
+            Range::Unsupported(value) | Range::Invalid(value) => value.clone(),
+        }
+    }
+}
+
+impl RequestHeader for Range {}
+
+/// The `Content-Range` header, RFC 7233 section 4.2, like
+/// `bytes 0-499/1234`, or `bytes */1234` in a `416` response
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContentRange {
+    range: Option<(u64, u64)>,
+    complete_length: Option<u64>,
+}
+
+impl ContentRange {
+    /// Bytes `first` to `last`, both inclusive, of a representation of
+    /// `complete_length` bytes, if known
+    pub fn bytes(
+        first: u64,
+        last: u64,
+        complete_length: Option<u64>,
+    ) -> Result<ContentRange, HttpErrors> {
+        let is_valid = match complete_length {
+            Some(length) => first <= last && last < length,
+            None => first <= last,
+        };
+        if !is_valid {
+            let range = format!("{}-{}", first, last);
+            return Err(HttpErrors::InvalidRange(range));
+        }
+
+        Ok(ContentRange {
+            range: Some((first, last)),
+            complete_length,
+        })
+    }
+
+    /// For a `416 Range Not Satisfiable` response
+    pub fn unsatisfied(complete_length: u64) -> ContentRange {
+        ContentRange {
+            range: None,
+            complete_length: Some(complete_length),
+        }
+    }
+
+    /// First and last byte, both inclusive. `None` if unsatisfied.
+    pub fn range(&self) -> Option<(u64, u64)> {
+        self.range
+    }
+
+    pub fn complete_length(&self) -> Option<u64> {
+        self.complete_length
+    }
+}
+
+impl TryFrom<&str> for ContentRange {
+    type Error = HttpErrors;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let invalid = || HttpErrors::InvalidRange(value.to_string());
+        let (unit, rest) = value.trim().split_once(' ').ok_or_else(invalid)?;
+        let (range, length) = rest.split_once('/').ok_or_else(invalid)?;
+        if !unit.eq_ignore_ascii_case("bytes") {
+            return Err(invalid());
+        }
+
+        let complete_length = match length {
+            "*" => None,
+            length => Some(parse_position(length).ok_or_else(invalid)?),
+        };
+
+        match (range, complete_length) {
+            ("*", Some(length)) => Ok(ContentRange::unsatisfied(length)),
+            ("*", None) => Err(invalid()),
+            (range, complete_length) => match ByteRange::try_from(range) {
+                Ok(ByteRange::FromTo(first, last)) => {
+                    ContentRange::bytes(first, last, complete_length)
+                        .map_err(|_| invalid())
+                }
+                _ => Err(invalid()),
+            },
+        }
+    }
+}
+
+impl TypedHeader for ContentRange {
+    const NAME: &'static str = CONTENT_RANGE_HEADER_NAME;
+
+    fn parse(value: &str) -> Result<Self, HttpErrors> {
+        ContentRange::try_from(value)
+    }
+
+    fn encode(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl ResponseHeader for ContentRange {}
+
+impl fmt::Display for ContentRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.range {
+            Some((first, last)) => write!(f, "bytes {}-{}/", first, last)?,
+            None => f.write_str("bytes */")?,
+        }
+
+        match self.complete_length {
+            Some(length) => write!(f, "{}", length),
+            None => f.write_str("*"),
+        }
+    }
+}
+
+/// The `If-Range` header, RFC 7233 section 3.2. A value which is neither
+/// an entity-tag nor an HTTP-date is kept as `Invalid` and never matches,
+/// so the whole representation is sent.
+#[derive(Clone, Debug, PartialEq)]
+pub enum IfRange {
+    ETag(EntityTag),
+    Date(SystemTime),
+    Invalid(String),
+}
+
+impl IfRange {
+    /// Whether the representation is unchanged, so the `Range` header
+    /// applies. Only strong validators match: weak entity-tags never do,
+    /// and dates have to equal the modification date exactly.
+    pub fn matches(&self, validators: &Validators) -> bool {
+        match self {
+            IfRange::ETag(tag) => {
+                validators.etag().is_some_and(|t| t.strong_eq(tag))
+            }
+            IfRange::Date(date) => validators.last_modified() == Some(*date),
+            IfRange::Invalid(_) => false,
+        }
+    }
+}
+
+impl TypedHeader for IfRange {
+    const NAME: &'static str = IF_RANGE_HEADER_NAME;
+
+    fn parse(value: &str) -> Result<Self, HttpErrors> {
+        let value = value.trim();
+        if let Ok(tag) = EntityTag::try_from(value) {
+            return Ok(IfRange::ETag(tag));
+        }
+
+        match parse_http_date(value) {
+            Some(date) => Ok(IfRange::Date(date)),
+            None => Ok(IfRange::Invalid(value.to_string())),
+        }
+    }
+
+    fn encode(&self) -> String {
+        match self {
+            IfRange::ETag(tag) => tag.to_string(),
+            IfRange::Date(date) => format_http_date(*date),
+            IfRange::Invalid(value) => value.clone(),
+        }
+    }
+}
+
+impl RequestHeader for IfRange {}
+
+#[cfg(test)]
+mod tests_range {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_parse_range() {
+        let range = Range::parse("bytes=0-499, 500- ,-200,").unwrap();
+        assert_eq!(
+            range,
+            Range::bytes(vec![
+                ByteRange::FromTo(0, 499),
+                ByteRange::From(500),
+                ByteRange::Last(200),
+            ])
+        );
+        assert_eq!(range.encode(), "bytes=0-499, 500-, -200");
+
+        let range = Range::parse("items=0-5").unwrap();
+        assert_eq!(range, Range::Unsupported("items=0-5".into()));
+        assert!(range.satisfiable(100).is_empty());
+
+        let range = Range::parse("0-5").unwrap();
+        assert_eq!(range, Range::Unsupported("0-5".into()));
+
+        for value in ["bytes=", "bytes=5-1", "bytes=a-b", "bytes=-"] {
+            let range = Range::parse(value).unwrap();
+            assert_eq!(range, Range::Invalid(value.to_string()));
+            assert!(range.satisfiable(100).is_empty());
+            assert_eq!(range.encode(), value);
+        }
+    }
+
+    #[test]
+    fn test_satisfiable() {
+        let range = Range::parse("bytes=0-9, 95-200, 100-, -5, -0").unwrap();
+        assert_eq!(range.satisfiable(100), vec![(0, 9), (95, 99), (95, 99)]);
+        assert_eq!(range.satisfiable(3), vec![(0, 2), (0, 2)]);
+        assert!(range.satisfiable(0).is_empty());
+    }
+
+    #[test]
+    fn test_content_range() {
+        let range = ContentRange::bytes(0, 499, Some(1234)).unwrap();
+        assert_eq!(range.encode(), "bytes 0-499/1234");
+        assert_eq!(ContentRange::parse("bytes 0-499/1234").unwrap(), range);
+
+        let range = ContentRange::parse("bytes 42-1233/*").unwrap();
+        assert_eq!(range.range(), Some((42, 1233)));
+        assert_eq!(range.complete_length(), None);
+        assert_eq!(range.encode(), "bytes 42-1233/*");
+
+        let range = ContentRange::unsatisfied(1234);
+        assert_eq!(range.encode(), "bytes */1234");
+        assert_eq!(ContentRange::parse("bytes */1234").unwrap(), range);
+
+        assert!(ContentRange::bytes(5, 4, None).is_err());
+        assert!(ContentRange::bytes(0, 10, Some(10)).is_err());
+        for value in ["bytes */*", "bytes 0-/10", "items 0-1/2", "bytes 0-1"] {
+            assert!(ContentRange::parse(value).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_if_range() {
+        let time = UNIX_EPOCH + Duration::from_secs(784111777);
+        let validators = Validators::new()
+            .with_etag(EntityTag::strong("v1").unwrap())
+            .with_last_modified(time);
+
+        let if_range = IfRange::parse("\"v1\"").unwrap();
+        assert!(if_range.matches(&validators));
+        assert!(!IfRange::parse("W/\"v1\"").unwrap().matches(&validators));
+        assert!(!IfRange::parse("\"v2\"").unwrap().matches(&validators));
+
+        let if_range = IfRange::parse("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+        assert_eq!(if_range, IfRange::Date(time));
+        assert!(if_range.matches(&validators));
+        assert!(
+            !IfRange::Date(time + Duration::from_secs(1)).matches(&validators)
+        );
+        let if_range = IfRange::parse("yesterday").unwrap();
+        assert_eq!(if_range, IfRange::Invalid("yesterday".into()));
+        assert!(!if_range.matches(&validators));
+    }
+}
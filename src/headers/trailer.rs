@@ -1,23 +1,22 @@
 use crate::errors::Error as HttpErrors;
-use crate::headers::{GeneralHeader, Header, Headers, TRAILER_HEADER_NAME};
-use std::any::Any;
+use crate::headers::{
+    GeneralHeader, Headers, TypedHeader, TRAILER_HEADER_NAME,
+};
 use std::convert::TryFrom;
 
 pub struct Trailer {
     fields: Vec<String>,
 }
 
-impl Header for Trailer {
-    fn name(&self) -> &str {
-        TRAILER_HEADER_NAME
-    }
+impl TypedHeader for Trailer {
+    const NAME: &'static str = TRAILER_HEADER_NAME;
 
-    fn value(&self) -> String {
-        self.fields.join(", ")
+    fn parse(value: &str) -> Result<Self, HttpErrors> {
+        Trailer::try_from(value)
     }
 
-    fn as_any(&self) -> &dyn Any {
-        self
+    fn encode(&self) -> String {
+        self.fields.join(", ")
     }
 }
 
@@ -1,6 +1,7 @@
 use crate::errors::Error as HttpErrors;
-use crate::headers::{GeneralHeader, Header, TRANSFER_ENCODING_HEADER_NAME};
-use std::any::Any;
+use crate::headers::{
+    GeneralHeader, TypedHeader, TRANSFER_ENCODING_HEADER_NAME,
+};
 use std::convert::TryFrom;
 
 pub struct TransferEncoding {
@@ -29,29 +30,28 @@ impl TryFrom<&str> for TransferEncoding {
         let value: Vec<&str> = value.split(",").collect();
         let value = value
             .iter()
-            .map(|v| v.trim())
-            .map(TransferEncodingValue::from)
+            // transfer-coding names are case-insensitive
+            .map(|v| v.trim().to_ascii_lowercase())
+            .map(|v| TransferEncodingValue::from(&v[..]))
             .collect();
 
         Ok(TransferEncoding { encodings: value })
     }
 }
 
-impl Header for TransferEncoding {
-    fn name(&self) -> &str {
-        TRANSFER_ENCODING_HEADER_NAME
+impl TypedHeader for TransferEncoding {
+    const NAME: &'static str = TRANSFER_ENCODING_HEADER_NAME;
+
+    fn parse(value: &str) -> Result<Self, HttpErrors> {
+        TransferEncoding::try_from(value)
     }
 
-    fn value(&self) -> String {
+    fn encode(&self) -> String {
         let list: Vec<&str> =
             self.encodings.iter().map(|e| e.to_string()).collect();
 
         list.join(", ")
     }
-
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
 }
 
 impl GeneralHeader for TransferEncoding {}
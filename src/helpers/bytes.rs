@@ -216,6 +216,16 @@ impl FragmentedBytes {
         return vector;
     }
 
+    /// Copies the unread bytes into one buffer
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut vector = Vec::with_capacity(self.total_len());
+        for bytes in &self.bytes_vec {
+            vector.extend_from_slice(&bytes.buffer()[..bytes.len()]);
+        }
+
+        vector.split_off(self.read_pos().min(vector.len()))
+    }
+
     pub fn remaining_bytes(self) -> FragmentedBytes {
         let mut bytes_vec = vec![];
         let read_pos = self.read_pos();
@@ -355,6 +365,20 @@ mod bytes_test {
         }
     }
 
+    #[test]
+    fn test_to_vec() {
+        let mut fragmented_bytes = create_fragmented_bytes();
+        let expected_vector =
+            vec![1, 2, 3, 4, 5, 6, 7, 8, 11, 12, 13, 14, 15, 16, 17, 18];
+        assert_eq!(fragmented_bytes.to_vec(), expected_vector);
+
+        fragmented_bytes.set_read_pos(6);
+        assert_eq!(
+            fragmented_bytes.to_vec(),
+            vec![7, 8, 11, 12, 13, 14, 15, 16, 17, 18]
+        );
+    }
+
     #[test]
     fn test_remaining_bytes() {
         let expected_vector =
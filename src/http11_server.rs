@@ -1,24 +1,291 @@
-use crate::app::App;
-use crate::connection::Connection;
+use crate::app::{App, SharedApp};
+use crate::connection::{Connection, ConnectionInfo};
+use crate::http11_server_builder::Http11ServerBuilder;
+use crate::listener::ListenerSource;
+use crate::socket_options::SocketOptions;
 use crate::{Request, RequestBuilder};
-use std::io::{ErrorKind, Result};
-use std::sync::{Arc, Mutex};
+use core_affinity::CoreId;
+use futures::channel::oneshot;
+use futures::future::{
+    try_join_all, AbortHandle, AbortRegistration, Abortable, Aborted,
+};
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::io::{Error, Result};
+use std::sync::Arc;
+use std::thread;
 use tokio::net::{TcpListener, TcpStream};
+use tokio::runtime;
 
-pub struct Http11Server {}
+/// Settings shared by every accept loop of a server
+#[derive(Clone, Debug)]
+pub(crate) struct ServerConfig {
+    pub(crate) proxy_protocol: bool,
+    pub(crate) socket_options: SocketOptions,
+}
+
+pub struct Http11Server {
+    listeners: Vec<ListenerSource>,
+    workers: Option<usize>,
+    config: ServerConfig,
+}
 
 impl Http11Server {
-    pub async fn start<'a>(app: App) -> Result<()> {
-        let listener = TcpListener::bind("127.0.0.1:8080").await?;
+    pub(crate) fn new(
+        listeners: Vec<ListenerSource>,
+        workers: Option<usize>,
+        config: ServerConfig,
+    ) -> Self {
+        Http11Server {
+            listeners,
+            workers,
+            config,
+        }
+    }
+
+    pub fn builder() -> Http11ServerBuilder {
+        Http11ServerBuilder::new()
+    }
+
+    /// Starts a server with the default configuration
+    pub async fn start(app: App) -> Result<()> {
+        Http11Server::builder().build().serve(app).await
+    }
+
+    pub async fn serve(self, app: App) -> Result<()> {
+        let Http11Server {
+            listeners,
+            workers,
+            config,
+        } = self;
+
+        let app = Arc::new(app);
+        match workers {
+            None => {
+                Http11Server::serve_listeners(listeners, app, config, false)
+                    .await
+            }
+            Some(workers) => {
+                Http11Server::serve_thread_per_core(
+                    listeners, app, config, workers,
+                )
+                .await
+            }
+        }
+    }
+
+    async fn serve_listeners(
+        sources: Vec<ListenerSource>,
+        app: SharedApp,
+        config: ServerConfig,
+        reuse_port: bool,
+    ) -> Result<()> {
+        let backlog = config.socket_options.listen_backlog;
+        let mut listeners = Vec::with_capacity(sources.len());
+        for source in sources {
+            listeners.push(source.into_listener(reuse_port, backlog).await?);
+        }
+
+        let accept_loops = listeners.iter().map(|listener| {
+            Http11Server::accept_loop(listener, app.clone(), &config)
+        });
+
+        try_join_all(accept_loops).await?;
+        Ok(())
+    }
+
+    async fn serve_thread_per_core(
+        sources: Vec<ListenerSource>,
+        app: SharedApp,
+        config: ServerConfig,
+        workers: usize,
+    ) -> Result<()> {
+        let core_ids = core_affinity::get_core_ids().unwrap_or_default();
+        let backlog = config.socket_options.listen_backlog;
+
+        // the first worker gets the sockets bound here, the others bind the
+        // same concrete addresses, even if port 0 was configured
+        let mut first_sources = Vec::with_capacity(sources.len());
+        let mut shared_sources = Vec::with_capacity(sources.len());
+        for source in sources {
+            let (bound, shared) = source.bind_reuse_port(backlog)?;
+            first_sources.push(bound);
+            shared_sources.push(shared);
+        }
+
+        let mut worker_sources = Vec::with_capacity(workers);
+        worker_sources.push(first_sources);
+        for _ in 1..workers {
+            let sources = shared_sources
+                .iter()
+                .map(ListenerSource::try_clone)
+                .collect::<Result<Vec<_>>>()?;
+            worker_sources.push(sources);
+        }
+
+        let mut results = FuturesUnordered::new();
+        let mut abort_handles = Vec::with_capacity(workers);
+        for (worker, sources) in worker_sources.into_iter().enumerate() {
+            let app = app.clone();
+            let config = config.clone();
+            let core_id = match core_ids.len() {
+                0 => None,
+                len => Some(core_ids[worker % len]),
+            };
+
+            let (abort_handle, registration) = AbortHandle::new_pair();
+            let (sender, receiver) = oneshot::channel();
+            let spawned = thread::Builder::new()
+                .name(format!("http11-worker-{}", worker))
+                .spawn(move || {
+                    let result = Http11Server::run_worker(
+                        sources,
+                        app,
+                        config,
+                        core_id,
+                        registration,
+                    );
+                    let _ = sender.send(result);
+                });
+
+            if let Err(error) = spawned {
+                abort_handles.iter().for_each(AbortHandle::abort);
+                return Err(error);
+            }
 
-        let app = Arc::new(Mutex::new(App {}));
+            abort_handles.push(abort_handle);
+            results.push(receiver);
+        }
+
+        // a failing worker stops all the others, which would otherwise keep
+        // serving on their own
+        let mut first_error = None;
+        while let Some(result) = results.next().await {
+            let result = result.unwrap_or_else(|_| {
+                Err(Error::other("worker thread panicked"))
+            });
+            if let Err(error) = result {
+                abort_handles.iter().for_each(AbortHandle::abort);
+                first_error.get_or_insert(error);
+            }
+        }
+
+        first_error.map_or(Ok(()), Err)
+    }
+
+    fn run_worker(
+        sources: Vec<ListenerSource>,
+        app: SharedApp,
+        config: ServerConfig,
+        core_id: Option<CoreId>,
+        registration: AbortRegistration,
+    ) -> Result<()> {
+        if let Some(core_id) = core_id {
+            // pinning is best effort, the worker still runs if it fails
+            core_affinity::set_for_current(core_id);
+        }
 
+        let runtime = runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+
+        let serve = Http11Server::serve_listeners(sources, app, config, true);
+        match runtime.block_on(Abortable::new(serve, registration)) {
+            Ok(result) => result,
+            // stopped because another worker failed
+            Err(Aborted) => Ok(()),
+        }
+    }
+
+    async fn accept_loop(
+        listener: &TcpListener,
+        app: SharedApp,
+        config: &ServerConfig,
+    ) -> Result<()> {
         loop {
-            let (stream, _) = listener.accept().await?;
+            let (stream, peer_addr) = listener.accept().await?;
+            if config.socket_options.apply(&stream).is_err() {
+                // the socket is unusable, drop the connection
+                continue;
+            }
+
+            let local_addr = match stream.local_addr() {
+                Ok(a) => a,
+                // the connection is already gone, drop it
+                Err(_) => continue,
+            };
+
             let app = app.clone();
-            let mut connection = Connection::new(stream, app);
+            let info = ConnectionInfo::new(peer_addr, local_addr);
+            let mut connection =
+                Connection::new(stream, app, info, config.proxy_protocol);
             connection.process_socket().await;
             println!("connection closed");
         }
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests_http11_server {
+    use super::*;
+    use std::os::unix::io::OwnedFd;
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    fn config() -> ServerConfig {
+        ServerConfig {
+            proxy_protocol: false,
+            socket_options: SocketOptions::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_thread_per_core_serves() {
+        let sources = vec![ListenerSource::Address("127.0.0.1:0".into())];
+        let app = Arc::new(App::new());
+        let serve =
+            Http11Server::serve_thread_per_core(sources, app, config(), 2);
+
+        // every worker bound the same port and keeps accepting
+        let result = timeout(Duration::from_millis(200), serve).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_thread_per_core_failing_worker() {
+        // accepting on a socket which is not listening fails in every
+        // worker
+        let socket = socket2::Socket::new(
+            socket2::Domain::IPV4,
+            socket2::Type::STREAM,
+            None,
+        )
+        .unwrap();
+        let sources = vec![
+            ListenerSource::Address("127.0.0.1:0".into()),
+            ListenerSource::Fd(OwnedFd::from(socket)),
+        ];
+        let app = Arc::new(App::new());
+        let serve =
+            Http11Server::serve_thread_per_core(sources, app, config(), 2);
+
+        let result = timeout(Duration::from_secs(5), serve).await;
+        assert!(result.expect("the workers were not stopped").is_err());
+    }
+
+    #[test]
+    fn test_aborted_worker_stops() {
+        let sources = vec![ListenerSource::Address("127.0.0.1:0".into())];
+        let app = Arc::new(App::new());
+        let (abort_handle, registration) = AbortHandle::new_pair();
+        abort_handle.abort();
+
+        let result = Http11Server::run_worker(
+            sources,
+            app,
+            config(),
+            None,
+            registration,
+        );
+        assert!(result.is_ok());
+    }
+}
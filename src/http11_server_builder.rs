@@ -0,0 +1,148 @@
+use crate::http11_server::{Http11Server, ServerConfig};
+use crate::listener::ListenerSource;
+use crate::socket_options::SocketOptions;
+use std::io::Result;
+#[cfg(unix)]
+use std::os::unix::io::OwnedFd;
+use std::time::Duration;
+
+static DEFAULT_ADDRESS: &str = "127.0.0.1:8080";
+
+pub struct Http11ServerBuilder {
+    listeners: Vec<ListenerSource>,
+    proxy_protocol: bool,
+    workers: Option<usize>,
+    socket_options: SocketOptions,
+}
+
+impl Http11ServerBuilder {
+    pub fn new() -> Http11ServerBuilder {
+        Http11ServerBuilder {
+            listeners: vec![],
+            proxy_protocol: false,
+            workers: None,
+            socket_options: SocketOptions::new(),
+        }
+    }
+
+    /// Listen on `address`. Can be called multiple times to listen on
+    /// several addresses. If no listener is configured at all, the server
+    /// binds to `127.0.0.1:8080`.
+    pub fn bind(mut self, address: &str) -> Self {
+        self.listeners
+            .push(ListenerSource::Address(String::from(address)));
+        self
+    }
+
+    /// Listen on an already bound and listening socket, for example one
+    /// inherited from the parent process across a restart.
+    #[cfg(unix)]
+    pub fn listener_fd(mut self, fd: OwnedFd) -> Self {
+        self.listeners.push(ListenerSource::Fd(fd));
+        self
+    }
+
+    /// Listen on the sockets passed by systemd socket activation through
+    /// `LISTEN_FDS` and `LISTEN_PID`. Nothing is added if the process was
+    /// not socket activated, or by any call but the first.
+    #[cfg(unix)]
+    pub fn systemd_listeners(mut self) -> Result<Self> {
+        let fds = crate::listener::systemd_listen_fds()?;
+        self.listeners
+            .extend(fds.into_iter().map(ListenerSource::Fd));
+        Ok(self)
+    }
+
+    /// Expect every accepted connection to start with a HAProxy PROXY
+    /// protocol (v1 or v2) header. Connections without a valid header, or
+    /// which do not send it within 5 seconds, are closed. Only enable this behind a proxy which always sends one.
+    pub fn proxy_protocol(mut self, enabled: bool) -> Self {
+        self.proxy_protocol = enabled;
+        self
+    }
+
+    /// Run `workers` accept loops instead of one, each on its own
+    /// current-thread runtime pinned to a core. Every loop binds the
+    /// configured addresses with `SO_REUSEPORT`, so the kernel balances new
+    /// connections across them. Port 0 is resolved once, so all loops share
+    /// the same port. Inherited sockets are shared by all loops. If one loop
+    /// fails, the others are stopped.
+    pub fn thread_per_core(mut self, workers: usize) -> Self {
+        self.workers = Some(workers.max(1));
+        self
+    }
+
+    /// Maximum number of pending connections of the bound listeners,
+    /// 1024 by default
+    pub fn listen_backlog(mut self, backlog: u32) -> Self {
+        self.socket_options.listen_backlog = backlog;
+        self
+    }
+
+    /// Set `TCP_NODELAY` on accepted connections to disable Nagle's
+    /// algorithm
+    pub fn tcp_nodelay(mut self, nodelay: bool) -> Self {
+        self.socket_options.nodelay = Some(nodelay);
+        self
+    }
+
+    /// Enable TCP keepalive on accepted connections, sending the first probe
+    /// after the connection has been idle for `time`
+    pub fn tcp_keepalive(mut self, time: Duration) -> Self {
+        self.socket_options.keepalive_time = Some(time);
+        self
+    }
+
+    /// Time between two TCP keepalive probes (`TCP_KEEPINTVL`). Only
+    /// applied on Linux, macOS and Windows.
+    pub fn tcp_keepalive_interval(mut self, interval: Duration) -> Self {
+        self.socket_options.keepalive_interval = Some(interval);
+        self
+    }
+
+    /// Number of unanswered TCP keepalive probes after which the connection
+    /// is dropped (`TCP_KEEPCNT`). Only applied on Linux, macOS and Windows.
+    pub fn tcp_keepalive_retries(mut self, retries: u32) -> Self {
+        self.socket_options.keepalive_retries = Some(retries);
+        self
+    }
+
+    /// `SO_SNDBUF` of accepted connections
+    pub fn send_buffer_size(mut self, size: usize) -> Self {
+        self.socket_options.send_buffer_size = Some(size);
+        self
+    }
+
+    /// `SO_RCVBUF` of accepted connections
+    pub fn recv_buffer_size(mut self, size: usize) -> Self {
+        self.socket_options.recv_buffer_size = Some(size);
+        self
+    }
+
+    pub fn build(self) -> Http11Server {
+        let Http11ServerBuilder {
+            mut listeners,
+            proxy_protocol,
+            workers,
+            socket_options,
+        } = self;
+
+        if listeners.is_empty() {
+            listeners
+                .push(ListenerSource::Address(String::from(DEFAULT_ADDRESS)));
+        }
+
+        let config = ServerConfig {
+            proxy_protocol,
+            socket_options,
+        };
+
+        Http11Server::new(listeners, workers, config)
+    }
+}
+
+impl Default for Http11ServerBuilder {
+    fn default() -> Self {
+        Http11ServerBuilder::new()
+    }
+}
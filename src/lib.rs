@@ -6,7 +6,9 @@
 #[macro_use]
 extern crate lazy_static;
 
+mod cookie_jar;
 pub mod errors;
+mod extensions;
 mod grammar;
 pub mod headers;
 #[macro_use]
@@ -14,10 +16,22 @@ mod helpers;
 pub mod app;
 mod connection;
 mod http11_server;
+mod http11_server_builder;
+mod listener;
 mod request;
 pub mod response;
+mod socket_options;
 pub mod status;
 
 pub use crate::http11_server::*;
+pub use crate::http11_server_builder::*;
 pub use app::App;
-pub use request::{Request, RequestBuilder};
+pub use connection::{ConnectionInfo, ProxyHeader};
+pub use cookie_jar::CookieJar;
+#[cfg(feature = "secure-cookies")]
+pub use cookie_jar::{Key, KeyRing, PrivateJar, SignedJar};
+pub use extensions::Extensions;
+pub use request::{
+    DecodeOptions, HttpMethods, HttpVersion, QueryParams, Request, RequestBody,
+    RequestBuilder, RequestTargetForm, RequestUri,
+};
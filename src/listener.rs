@@ -0,0 +1,425 @@
+use std::env;
+use std::io::{Error, ErrorKind, Result};
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::ops::Range;
+#[cfg(unix)]
+use std::os::unix::io::{FromRawFd, OwnedFd, RawFd};
+use std::process;
+#[cfg(unix)]
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::net::{TcpListener, TcpSocket};
+
+/// `SD_LISTEN_FDS_START`, the first file descriptor passed by systemd
+#[cfg(unix)]
+const LISTEN_FDS_START: RawFd = 3;
+
+/// Whether the systemd sockets were already taken, they have a single owner
+#[cfg(unix)]
+static LISTEN_FDS_TAKEN: AtomicBool = AtomicBool::new(false);
+
+/// Where the listening socket of a server comes from
+pub(crate) enum ListenerSource {
+    /// A fresh socket bound to the given address
+    Address(String),
+    /// An already bound and listening socket inherited from the parent
+    /// process or handed over by the application
+    #[cfg(unix)]
+    Fd(OwnedFd),
+}
+
+impl ListenerSource {
+    /// Binds or adopts the socket. With `reuse_port`, addresses are bound
+    /// with `SO_REUSEPORT` so that several listeners can share them.
+    /// `backlog` only applies to sockets bound here, inherited sockets are
+    /// already listening.
+    pub(crate) async fn into_listener(
+        self,
+        reuse_port: bool,
+        backlog: u32,
+    ) -> Result<TcpListener> {
+        match self {
+            ListenerSource::Address(address) => {
+                bind(&address, reuse_port, backlog)
+            }
+            #[cfg(unix)]
+            ListenerSource::Fd(fd) => {
+                let listener = std::net::TcpListener::from(fd);
+                listener.set_nonblocking(true)?;
+                TcpListener::from_std(listener)
+            }
+        }
+    }
+
+    /// A source for one more listener on the same address or socket.
+    /// Inherited sockets are duplicated, so their listeners share a single
+    /// accept queue.
+    pub(crate) fn try_clone(&self) -> Result<ListenerSource> {
+        match self {
+            ListenerSource::Address(address) => {
+                Ok(ListenerSource::Address(address.clone()))
+            }
+            #[cfg(unix)]
+            ListenerSource::Fd(fd) => Ok(ListenerSource::Fd(fd.try_clone()?)),
+        }
+    }
+
+    /// Binds an address right away with `SO_REUSEPORT`, so that port 0 is
+    /// resolved only once. Returns the bound socket, and a source for the
+    /// other listeners on the same concrete address.
+    pub(crate) fn bind_reuse_port(
+        self,
+        backlog: u32,
+    ) -> Result<(ListenerSource, ListenerSource)> {
+        match self {
+            #[cfg(unix)]
+            ListenerSource::Address(address) => {
+                let listener = bind(&address, true, backlog)?;
+                let address = listener.local_addr()?.to_string();
+                let fd = OwnedFd::from(listener.into_std()?);
+                Ok((ListenerSource::Fd(fd), ListenerSource::Address(address)))
+            }
+            source => Ok((source.try_clone()?, source)),
+        }
+    }
+}
+
+fn bind(address: &str, reuse_port: bool, backlog: u32) -> Result<TcpListener> {
+    bind_any(address.to_socket_addrs()?, reuse_port, backlog).map_err(|e| {
+        e.unwrap_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("could not resolve {}", address),
+            )
+        })
+    })
+}
+
+/// Tries every address in turn, like `TcpListener::bind`. Fails with the
+/// error of the last one, or `None` if there is none.
+fn bind_any(
+    addresses: impl Iterator<Item = SocketAddr>,
+    reuse_port: bool,
+    backlog: u32,
+) -> std::result::Result<TcpListener, Option<Error>> {
+    let mut last_error = None;
+    for address in addresses {
+        match bind_addr(address, reuse_port, backlog) {
+            Ok(listener) => return Ok(listener),
+            Err(error) => last_error = Some(error),
+        }
+    }
+
+    Err(last_error)
+}
+
+fn bind_addr(
+    address: SocketAddr,
+    reuse_port: bool,
+    backlog: u32,
+) -> Result<TcpListener> {
+    let socket = match address {
+        SocketAddr::V4(_) => TcpSocket::new_v4()?,
+        SocketAddr::V6(_) => TcpSocket::new_v6()?,
+    };
+
+    #[cfg(unix)]
+    socket.set_reuseaddr(true)?;
+
+    if reuse_port {
+        set_reuse_port(&socket)?;
+    }
+
+    socket.bind(address)?;
+    socket.listen(backlog)
+}
+
+#[cfg(unix)]
+fn set_reuse_port(socket: &TcpSocket) -> Result<()> {
+    socket.set_reuseport(true)
+}
+
+#[cfg(not(unix))]
+fn set_reuse_port(socket: &TcpSocket) -> Result<()> {
+    Err(Error::new(
+        ErrorKind::Unsupported,
+        "SO_REUSEPORT is only supported on unix",
+    ))
+}
+
+/// Takes ownership of the sockets passed by systemd socket activation
+/// (`LISTEN_PID` and `LISTEN_FDS`), in the order of the `.socket` unit.
+///
+/// Only the first call returns them. The environment is left as it is,
+/// changing it is unsound while other threads may read it. Child processes
+/// ignore the variables as `LISTEN_PID` is not theirs, and do not inherit
+/// the sockets, which are closed on exec. Returns an empty list if the
+/// process was not socket activated.
+#[cfg(unix)]
+pub(crate) fn systemd_listen_fds() -> Result<Vec<OwnedFd>> {
+    let listen_pid = env::var("LISTEN_PID").ok();
+    let listen_fds = env::var("LISTEN_FDS").ok();
+    let fds = parse_listen_fds(
+        listen_pid.as_deref(),
+        listen_fds.as_deref(),
+        process::id(),
+    )?;
+
+    if fds.is_empty() || LISTEN_FDS_TAKEN.swap(true, Ordering::SeqCst) {
+        return Ok(vec![]);
+    }
+
+    fds.map(|fd| {
+        let fd = unsafe {
+            // systemd guarantees these are open and owned by this process,
+            // and the flag above makes sure they are adopted only once
+            OwnedFd::from_raw_fd(fd)
+        };
+        close_on_exec(fd)
+    })
+    .collect()
+}
+
+/// Sets `FD_CLOEXEC` like `sd_listen_fds` does, so that inherited sockets
+/// are not passed on to child processes
+#[cfg(unix)]
+fn close_on_exec(fd: OwnedFd) -> Result<OwnedFd> {
+    let socket = socket2::Socket::from(fd);
+    socket.set_cloexec(true)?;
+    Ok(OwnedFd::from(socket))
+}
+
+#[cfg(unix)]
+fn parse_listen_fds(
+    listen_pid: Option<&str>,
+    listen_fds: Option<&str>,
+    pid: u32,
+) -> Result<Range<RawFd>> {
+    let none = LISTEN_FDS_START..LISTEN_FDS_START;
+    let (listen_pid, listen_fds) = match (listen_pid, listen_fds) {
+        (Some(p), Some(f)) => (p, f),
+        _ => return Ok(none),
+    };
+
+    let listen_pid = listen_pid.parse::<u32>().map_err(|_| {
+        Error::new(ErrorKind::InvalidInput, "LISTEN_PID is not a number")
+    })?;
+
+    // the variables were meant for another process
+    if listen_pid != pid {
+        return Ok(none);
+    }
+
+    let listen_fds = listen_fds.parse::<RawFd>().map_err(|_| {
+        Error::new(ErrorKind::InvalidInput, "LISTEN_FDS is not a number")
+    })?;
+
+    if listen_fds < 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "LISTEN_FDS is negative",
+        ));
+    }
+
+    let end = LISTEN_FDS_START.checked_add(listen_fds).ok_or_else(|| {
+        Error::new(ErrorKind::InvalidInput, "LISTEN_FDS is too large")
+    })?;
+
+    Ok(LISTEN_FDS_START..end)
+}
+
+#[cfg(all(test, unix))]
+mod tests_listener {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::os::unix::io::{AsRawFd, IntoRawFd};
+    use std::os::unix::process::CommandExt;
+    use std::process::{Command, Stdio};
+
+    /// Set in the child process of `test_systemd_listen_fds_in_child`
+    const CHILD_ADDRESS: &str = "HTTP11_TEST_LISTEN_ADDRESS";
+
+    #[test]
+    fn test_parse_listen_fds() {
+        let fds = parse_listen_fds(Some("42"), Some("2"), 42).unwrap();
+        assert_eq!(fds, 3..5);
+
+        let fds = parse_listen_fds(Some("42"), Some("0"), 42).unwrap();
+        assert!(fds.is_empty());
+    }
+
+    #[test]
+    fn test_parse_listen_fds_other_process() {
+        let fds = parse_listen_fds(Some("41"), Some("2"), 42).unwrap();
+        assert!(fds.is_empty());
+
+        let fds = parse_listen_fds(None, Some("2"), 42).unwrap();
+        assert!(fds.is_empty());
+
+        let fds = parse_listen_fds(None, None, 42).unwrap();
+        assert!(fds.is_empty());
+    }
+
+    #[test]
+    fn test_parse_listen_fds_invalid() {
+        assert!(parse_listen_fds(Some("abc"), Some("2"), 42).is_err());
+        assert!(parse_listen_fds(Some("42"), Some("two"), 42).is_err());
+        assert!(parse_listen_fds(Some("42"), Some("-1"), 42).is_err());
+
+        let max = RawFd::MAX.to_string();
+        let error = parse_listen_fds(Some("42"), Some(&max), 42).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_close_on_exec() {
+        let bound = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let socket = socket2::Socket::from(OwnedFd::from(bound));
+        socket.set_cloexec(false).unwrap();
+
+        let fd = close_on_exec(OwnedFd::from(socket)).unwrap();
+        let flags = unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_GETFD) };
+        assert_eq!(flags & libc::FD_CLOEXEC, libc::FD_CLOEXEC);
+    }
+
+    #[tokio::test]
+    async fn test_listener_from_inherited_fd() {
+        let bound = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = bound.local_addr().unwrap();
+        let raw_fd = bound.into_raw_fd();
+
+        // as if the descriptor was inherited from a parent process
+        let fd = unsafe { OwnedFd::from_raw_fd(raw_fd) };
+        let listener = ListenerSource::Fd(fd)
+            .into_listener(false, 128)
+            .await
+            .unwrap();
+        assert_eq!(listener.as_raw_fd(), raw_fd);
+        assert_eq!(listener.local_addr().unwrap(), address);
+
+        let client = std::thread::spawn(move || {
+            let mut stream = std::net::TcpStream::connect(address).unwrap();
+            stream.write_all(b"ping").unwrap();
+        });
+
+        let (stream, _) = listener.accept().await.unwrap();
+        client.join().unwrap();
+
+        let mut stream = stream.into_std().unwrap();
+        stream.set_nonblocking(false).unwrap();
+        let mut buffer = String::new();
+        stream.read_to_string(&mut buffer).unwrap();
+        assert_eq!(buffer, "ping");
+    }
+
+    #[test]
+    fn test_systemd_listen_fds_in_child() {
+        let bound = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = bound.local_addr().unwrap();
+        let fd = bound.as_raw_fd();
+
+        // like systemd, pass the socket as fd 3 without FD_CLOEXEC, and
+        // set LISTEN_PID in a shell which then execs the test binary, so
+        // that the pid is the one of the child
+        let mut child = Command::new("sh");
+        child
+            .arg("-c")
+            .arg("export LISTEN_PID=$$; exec \"$0\" \"$@\"")
+            .arg(env::current_exe().unwrap())
+            .args(["--exact", "listener::tests_listener::child_listener"])
+            .arg("--test-threads=1")
+            .stdout(Stdio::null())
+            .env("LISTEN_FDS", "1")
+            .env(CHILD_ADDRESS, address.to_string());
+        unsafe {
+            child.pre_exec(move || {
+                let result = if fd == LISTEN_FDS_START {
+                    libc::fcntl(fd, libc::F_SETFD, 0)
+                } else {
+                    libc::dup2(fd, LISTEN_FDS_START)
+                };
+                match result {
+                    -1 => Err(Error::last_os_error()),
+                    _ => Ok(()),
+                }
+            });
+        }
+        let mut child = child.spawn().unwrap();
+        drop(bound);
+
+        let mut stream = std::net::TcpStream::connect(address).unwrap();
+        let mut buffer = String::new();
+        stream.read_to_string(&mut buffer).unwrap();
+        assert!(child.wait().unwrap().success());
+        assert_eq!(buffer, "inherited");
+    }
+
+    /// Runs in the child process of `test_systemd_listen_fds_in_child`,
+    /// and does nothing in a regular test run
+    #[test]
+    fn child_listener() {
+        let address = match env::var(CHILD_ADDRESS) {
+            Ok(address) => address,
+            Err(_) => return,
+        };
+
+        let mut fds = systemd_listen_fds().unwrap();
+        assert_eq!(fds.len(), 1);
+        // the sockets have a single owner
+        assert!(systemd_listen_fds().unwrap().is_empty());
+
+        let listener = std::net::TcpListener::from(fds.remove(0));
+        assert_eq!(listener.local_addr().unwrap().to_string(), address);
+        let (mut stream, _) = listener.accept().unwrap();
+        stream.write_all(b"inherited").unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reuse_port_listeners() {
+        let source = ListenerSource::Address("127.0.0.1:0".into());
+        let first = source.into_listener(true, 128).await.unwrap();
+        let address = first.local_addr().unwrap().to_string();
+
+        let source = ListenerSource::Address(address.clone());
+        let second = source.try_clone().unwrap().into_listener(true, 128).await;
+        assert!(second.is_ok());
+
+        // without SO_REUSEPORT the address is still taken
+        let third = source.into_listener(false, 128).await;
+        assert!(third.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bind_tries_every_address() {
+        let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let taken = taken.local_addr().unwrap();
+        let free: SocketAddr = "127.0.0.1:0".parse().unwrap();
+
+        let listener = bind_any(vec![taken, free].into_iter(), false, 128);
+        assert_ne!(listener.unwrap().local_addr().unwrap(), taken);
+
+        let error = bind_any(vec![taken].into_iter(), false, 128);
+        let error = error.unwrap_err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::AddrInUse);
+
+        assert!(bind_any(vec![].into_iter(), false, 128)
+            .unwrap_err()
+            .is_none());
+        let error = bind("invalid", false, 128).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[tokio::test]
+    async fn test_bind_reuse_port_resolves_port_once() {
+        let source = ListenerSource::Address("127.0.0.1:0".into());
+        let (bound, shared) = source.bind_reuse_port(128).unwrap();
+        let first = bound.into_listener(true, 128).await.unwrap();
+        let address = first.local_addr().unwrap();
+        assert_ne!(address.port(), 0);
+
+        for _ in 0..2 {
+            let source = shared.try_clone().unwrap();
+            let listener = source.into_listener(true, 128).await.unwrap();
+            assert_eq!(listener.local_addr().unwrap(), address);
+        }
+    }
+}
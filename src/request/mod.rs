@@ -1,9 +1,13 @@
+mod percent_encoding;
+mod query;
 mod request;
 mod request_body;
 mod request_body_builder;
 mod request_builder;
 mod request_uri;
 
+pub use percent_encoding::DecodeOptions;
+pub use query::*;
 pub use request::*;
 pub use request_body::*;
 pub use request_body_builder::*;
@@ -0,0 +1,173 @@
+use crate::errors::Error as HttpError;
+use crate::grammar::is_hex_digit;
+
+/// Controls which octets may appear percent-encoded when decoding.
+///
+/// The default is strict: an encoded "/" would let a single path segment
+/// turn into several after decoding, and an encoded NUL truncates strings
+/// handed to the operating system.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DecodeOptions {
+    pub allow_encoded_slash: bool,
+    pub allow_encoded_nul: bool,
+}
+
+/// Decodes pct-encoded octets in `value`, failing on malformed escapes,
+/// forbidden octets and decoded bytes which are not utf-8
+pub(crate) fn percent_decode(
+    value: &str,
+    options: DecodeOptions,
+) -> Result<String, HttpError> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] != b'%' {
+            decoded.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+
+        let is_escape = i + 2 < bytes.len()
+            && is_hex_digit(bytes[i + 1])
+            && is_hex_digit(bytes[i + 2]);
+        if !is_escape {
+            return Err(HttpError::InvalidPercentEncoding(format!(
+                "malformed escape in {}",
+                value
+            )));
+        }
+
+        let byte = hex_value(bytes[i + 1]) << 4 | hex_value(bytes[i + 2]);
+        if byte == b'/' && !options.allow_encoded_slash {
+            return Err(HttpError::InvalidPercentEncoding(format!(
+                "encoded slash in {}",
+                value
+            )));
+        }
+
+        if byte == 0 && !options.allow_encoded_nul {
+            return Err(HttpError::InvalidPercentEncoding(format!(
+                "encoded NUL in {}",
+                value
+            )));
+        }
+
+        decoded.push(byte);
+        i += 3;
+    }
+
+    String::from_utf8(decoded).map_err(|_| {
+        HttpError::InvalidPercentEncoding(format!(
+            "decoded value is not utf-8:- {}",
+            value
+        ))
+    })
+}
+
+/// Decodes every segment of an absolute path and then removes "." and ".."
+/// segments as in RFC 3986 section 5.2.4. Decoding first makes encoded dot
+/// segments like `%2e%2e` count as well. ".." never goes above the root.
+pub(crate) fn decode_segments(
+    path: &str,
+    options: DecodeOptions,
+) -> Result<Vec<String>, HttpError> {
+    let path = path.strip_prefix('/').unwrap_or(path);
+    let segments: Vec<&str> = path.split('/').collect();
+    let last = segments.len() - 1;
+    let mut output: Vec<String> = Vec::with_capacity(segments.len());
+
+    for (i, segment) in segments.into_iter().enumerate() {
+        let segment = percent_decode(segment, options)?;
+        match segment.as_str() {
+            "." => (),
+            ".." => {
+                output.pop();
+            }
+            _ => {
+                output.push(segment);
+                continue;
+            }
+        }
+
+        // "/a/b/.." is "/a/", keep the trailing slash
+        if i == last {
+            output.push(String::new());
+        }
+    }
+
+    Ok(output)
+}
+
+#[inline]
+fn hex_value(byte: u8) -> u8 {
+    match byte {
+        b'0'..=b'9' => byte - b'0',
+        b'a'..=b'f' => byte - b'a' + 10,
+        _ => byte - b'A' + 10,
+    }
+}
+
+#[cfg(test)]
+mod tests_percent_encoding {
+    use super::*;
+    use crate::assert_match_error;
+
+    fn normalize(path: &str) -> String {
+        let segments = decode_segments(path, DecodeOptions::default());
+        format!("/{}", segments.unwrap().join("/"))
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        let options = DecodeOptions::default();
+        assert_eq!(percent_decode("abc", options).unwrap(), "abc");
+        assert_eq!(percent_decode("a%20b%2B", options).unwrap(), "a b+");
+        assert_eq!(percent_decode("caf%C3%a9", options).unwrap(), "café");
+        assert_eq!(percent_decode("", options).unwrap(), "");
+    }
+
+    #[test]
+    fn test_percent_decode_invalid() {
+        let options = DecodeOptions::default();
+        for value in ["%", "%2", "a%2", "%zz", "%C3%28", "%%41"].iter() {
+            let result = percent_decode(value, options);
+            assert!(result.is_err(), "expected {:?} to be invalid", value);
+        }
+
+        let result = percent_decode("a%2fb", options);
+        let expected_error =
+            HttpError::InvalidPercentEncoding("encoded slash in a%2fb".into());
+        assert_match_error!(result.err().unwrap(), expected_error);
+
+        let result = percent_decode("a%00b", options);
+        let expected_error =
+            HttpError::InvalidPercentEncoding("encoded NUL in a%00b".into());
+        assert_match_error!(result.err().unwrap(), expected_error);
+    }
+
+    #[test]
+    fn test_percent_decode_allowed_octets() {
+        let options = DecodeOptions {
+            allow_encoded_slash: true,
+            allow_encoded_nul: true,
+        };
+        assert_eq!(percent_decode("a%2Fb", options).unwrap(), "a/b");
+        assert_eq!(percent_decode("a%00b", options).unwrap(), "a\0b");
+    }
+
+    #[test]
+    fn test_remove_dot_segments() {
+        assert_eq!(normalize("/a/b/c/./../../g"), "/a/g");
+        assert_eq!(normalize("/a/b/"), "/a/b/");
+        assert_eq!(normalize("/a/b/.."), "/a/");
+        assert_eq!(normalize("/a/b/."), "/a/b/");
+        assert_eq!(normalize("/"), "/");
+        assert_eq!(normalize("/.."), "/");
+        assert_eq!(normalize("/../../etc/passwd"), "/etc/passwd");
+        assert_eq!(normalize("/a/%2e%2E/%2e/b"), "/b");
+        assert_eq!(normalize("/a/..b/c."), "/a/..b/c.");
+        assert_eq!(normalize("/a//b"), "/a//b");
+    }
+}
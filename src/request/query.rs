@@ -0,0 +1,127 @@
+use crate::errors::Error as HttpError;
+use crate::request::percent_encoding::{percent_decode, DecodeOptions};
+use std::convert::TryFrom;
+
+/// Name/value pairs of an `application/x-www-form-urlencoded` query, in the
+/// order they appear. Repeated names keep all their values.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QueryParams {
+    pairs: Vec<(String, String)>,
+}
+
+impl QueryParams {
+    /// First value of `name`
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// All values of `name` in order, e.g. `["a", "b"]` for `?x=a&x=b`
+    pub fn get_all(&self, name: &str) -> Vec<&str> {
+        self.pairs
+            .iter()
+            .filter(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.pairs.iter().any(|(n, _)| n == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pairs.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+}
+
+impl TryFrom<&str> for QueryParams {
+    type Error = HttpError;
+
+    /// Parses `value` the way browsers encode forms: pairs are separated by
+    /// "&", a missing "=" means an empty value and "+" stands for a space
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let options = DecodeOptions {
+            allow_encoded_slash: true,
+            allow_encoded_nul: false,
+        };
+
+        let decode = |part: &str| {
+            percent_decode(&part.replace('+', " "), options)
+                .map_err(|e| HttpError::InvalidQuery(e.to_string()))
+        };
+
+        let mut pairs = vec![];
+        for pair in value.split('&').filter(|p| !p.is_empty()) {
+            let (name, value) = match pair.find('=') {
+                None => (pair, ""),
+                Some(i) => (&pair[..i], &pair[i + 1..]),
+            };
+
+            pairs.push((decode(name)?, decode(value)?));
+        }
+
+        Ok(QueryParams { pairs })
+    }
+}
+
+#[cfg(test)]
+mod tests_query {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let query =
+            QueryParams::try_from("a=1&b=two+words&c=%26%3D%2F").unwrap();
+        assert_eq!(query.len(), 3);
+        assert_eq!(query.get("a"), Some("1"));
+        assert_eq!(query.get("b"), Some("two words"));
+        assert_eq!(query.get("c"), Some("&=/"));
+        assert_eq!(query.get("d"), None);
+    }
+
+    #[test]
+    fn test_repeated_keys() {
+        let query = QueryParams::try_from("x=a&y=1&x=b&x=").unwrap();
+        assert_eq!(query.get("x"), Some("a"));
+        assert_eq!(query.get_all("x"), vec!["a", "b", ""]);
+        assert_eq!(query.get_all("z"), Vec::<&str>::new());
+
+        let pairs: Vec<(&str, &str)> = query.iter().collect();
+        assert_eq!(pairs, vec![("x", "a"), ("y", "1"), ("x", "b"), ("x", "")]);
+    }
+
+    #[test]
+    fn test_empty_values() {
+        let query = QueryParams::try_from("flag&empty=&=v&&").unwrap();
+        assert!(query.contains("flag"));
+        assert_eq!(query.get("flag"), Some(""));
+        assert_eq!(query.get("empty"), Some(""));
+        assert_eq!(query.get(""), Some("v"));
+        assert_eq!(query.len(), 3);
+
+        assert!(QueryParams::try_from("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_plus_and_encoded_plus() {
+        let query = QueryParams::try_from("q=1+%2B+1=2").unwrap();
+        assert_eq!(query.get("q"), Some("1 + 1=2"));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(QueryParams::try_from("a=%zz").is_err());
+        assert!(QueryParams::try_from("a=%C3%28").is_err());
+        assert!(QueryParams::try_from("a=%00").is_err());
+    }
+}
@@ -1,7 +1,11 @@
-use crate::headers::Headers;
+use crate::connection::{ConnectionInfo, ProxyHeader};
+use crate::extensions::Extensions;
+use crate::headers::{Headers, Validators};
 use crate::request::{HttpMethods, HttpVersion, RequestBody, RequestUri};
+use crate::status::StatusCode;
 use std::collections::LinkedList;
 use std::fmt;
+use std::net::SocketAddr;
 
 #[derive(Debug)]
 pub struct Request {
@@ -10,6 +14,8 @@ pub struct Request {
     http_version: HttpVersion,
     body: Option<RequestBody>,
     headers: Headers,
+    connection_info: Option<ConnectionInfo>,
+    extensions: Extensions,
 }
 
 impl Request {
@@ -26,8 +32,99 @@ impl Request {
             http_version,
             body,
             headers,
+            connection_info: None,
+            extensions: Extensions::new(),
         }
     }
+
+    pub fn method(&self) -> &HttpMethods {
+        &self.method
+    }
+
+    pub fn uri(&self) -> &RequestUri {
+        &self.uri
+    }
+
+    pub fn http_version(&self) -> &HttpVersion {
+        &self.http_version
+    }
+
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// Evaluates the conditional headers of the request against the
+    /// validators of the selected representation, see
+    /// [`Validators::evaluate`]
+    pub fn evaluate_preconditions(
+        &self,
+        validators: &Validators,
+    ) -> Option<StatusCode> {
+        validators.evaluate(&self.method, &self.headers)
+    }
+
+    /// The message body, `None` if the request had neither
+    /// `content-length` nor `transfer-encoding`
+    pub fn body(&self) -> Option<&RequestBody> {
+        self.body.as_ref()
+    }
+
+    /// Moves the body out of the request, leaving `None` behind
+    pub fn take_body(&mut self) -> Option<RequestBody> {
+        self.body.take()
+    }
+
+    /// Data attached to the request by middleware
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
+    /// The connection the request was received on. `None` for requests
+    /// which were not read from a socket.
+    pub fn connection_info(&self) -> Option<&ConnectionInfo> {
+        self.connection_info.as_ref()
+    }
+
+    /// Id of the connection, the same for all requests of a keep-alive
+    /// connection
+    pub fn connection_id(&self) -> Option<u64> {
+        self.connection_info.as_ref().map(|c| c.id())
+    }
+
+    /// Address of the TCP peer, which is the load balancer when the
+    /// server sits behind one
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.connection_info.as_ref().map(|c| c.peer_addr())
+    }
+
+    /// Address of the server socket which accepted the connection
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.connection_info.as_ref().map(|c| c.local_addr())
+    }
+
+    /// The PROXY protocol header received on this request's connection,
+    /// if the listener was configured to expect one
+    pub fn proxy_header(&self) -> Option<&ProxyHeader> {
+        self.connection_info.as_ref().and_then(|c| c.proxy_header())
+    }
+
+    /// Address of the client as relayed by the load balancer
+    pub fn original_source_addr(&self) -> Option<SocketAddr> {
+        self.proxy_header().and_then(|p| p.source())
+    }
+
+    /// Address the client connected to as relayed by the load balancer
+    pub fn original_destination_addr(&self) -> Option<SocketAddr> {
+        self.proxy_header().and_then(|p| p.destination())
+    }
+
+    pub(crate) fn set_connection_info(&mut self, info: ConnectionInfo) {
+        self.connection_info = Some(info);
+    }
 }
 
 // impl fmt::Debug for Request {
@@ -5,3 +5,15 @@ pub enum RequestBody {
     Whole(FragmentedBytes),
     Chunked(FragmentedBytes),
 }
+
+impl RequestBody {
+    /// Copies the body into one contiguous buffer. Chunked bodies are
+    /// returned without their chunk framing.
+    pub fn to_vec(&self) -> Vec<u8> {
+        match self {
+            RequestBody::Whole(bytes) | RequestBody::Chunked(bytes) => {
+                bytes.to_vec()
+            }
+        }
+    }
+}
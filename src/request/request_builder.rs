@@ -1,15 +1,17 @@
 use crate::errors::Error as HttpError;
 use crate::grammar::{is_token, is_token_char};
-use crate::headers::Headers;
+use crate::headers::{ContentLength, Headers, TransferEncoding, TypedHeader};
 use crate::helpers::bytes::{Bytes, FragmentedBytes};
 use crate::helpers::parser::*;
-use crate::request::{Request, RequestBodyBuilder, RequestUri};
+use crate::request::{
+    Request, RequestBodyBuilder, RequestTargetForm, RequestUri,
+};
 use std::collections::LinkedList;
 use std::convert::TryFrom;
 use std::vec::Vec;
 use std::{mem, str};
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum HttpMethods {
     GET,
     OPTIONS,
@@ -51,7 +53,7 @@ impl TryFrom<&str> for HttpMethods {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum HttpVersion {
     Http11,
 }
@@ -140,16 +142,38 @@ impl RequestBuilder {
         parts.pop();
 
         let request_uri = parts[1..].join(" ");
-        let request_uri = RequestUri::try_from(request_uri);
-        if request_uri.is_err() {
-            // TODO invalid request_uri
-            return Ok(self);
-        }
-        self.uri = Some(request_uri.unwrap());
+        let request_uri = RequestUri::try_from(request_uri)?;
+        RequestBuilder::check_target_form(
+            self.method.as_ref().unwrap(),
+            &request_uri,
+        )?;
+        self.uri = Some(request_uri);
 
         Ok(self)
     }
 
+    /// authority-form is only used by CONNECT and asterisk-form only by
+    /// OPTIONS, RFC 7230 section 5.3
+    fn check_target_form(
+        method: &HttpMethods,
+        uri: &RequestUri,
+    ) -> Result<(), HttpError> {
+        let is_valid = match uri.form() {
+            RequestTargetForm::Authority => {
+                matches!(method, HttpMethods::CONNECT)
+            }
+            RequestTargetForm::Asterisk => {
+                matches!(method, HttpMethods::OPTIONS)
+            }
+            _ => !matches!(method, HttpMethods::CONNECT),
+        };
+
+        match is_valid {
+            true => Ok(()),
+            false => Err(HttpError::InvalidUri),
+        }
+    }
+
     fn get_headers(&mut self) -> Result<Option<String>, HttpError> {
         let result = look_for_double_crlf(&mut self.fragmented_bytes);
         if result.is_none() {
@@ -183,53 +207,66 @@ impl RequestBuilder {
         Ok(self)
     }
 
-    fn create_request_body_builder(&mut self) {
-        if !self.are_headers_parsed()
-            || !self.can_have_body()
-            || self.body.is_some()
-        {
-            return;
+    fn create_request_body_builder(&mut self) -> Result<(), HttpError> {
+        if !self.are_headers_parsed() || self.body.is_some() {
+            return Ok(());
         }
 
-        let x = self.are_headers_valid();
+        self.are_headers_valid()?;
+        if !self.can_have_body() {
+            return Ok(());
+        }
 
         let fragmented_bytes = mem::take(&mut self.fragmented_bytes);
         self.fragmented_bytes = fragmented_bytes.remaining_bytes();
 
-        let is_chunked = self.is_chunked();
-        let body = match is_chunked {
-            true => RequestBodyBuilder::new_chunked(),
-            false => {
-                let headers = self.headers.as_ref().unwrap();
-                let content_length = headers.content_length();
-                let content_length = content_length.unwrap();
-                RequestBodyBuilder::new_whole(content_length.len())
-            }
+        let body = match self.content_length()? {
+            None => RequestBodyBuilder::new_chunked(),
+            Some(length) => RequestBodyBuilder::new_whole(length),
         };
         self.body = Some(body);
+        Ok(())
     }
 
-    fn are_headers_valid(&self) -> Result<bool, HttpError> {
-        let headers = self.headers.as_ref().unwrap();
+    /// Checks that the body is framed in only one way, RFC 7230 section
+    /// 3.3.3, so that no server on the way can read a different request
+    fn are_headers_valid(&self) -> Result<(), HttpError> {
+        let content_length = self.content_length()?;
 
-        let transfer_encoding = headers.transfer_encoding();
-        if let Some(transfer_encoding) = transfer_encoding {
-            if !transfer_encoding.is_chunked() {
-                return Err(HttpError::NoChunkedCoding);
+        match self.transfer_encoding() {
+            Some(_) if content_length.is_some() => {
+                Err(HttpError::ContentLengthWithTransferEncoding)
             }
-
-            return Ok(true);
+            Some(transfer_encoding) if !transfer_encoding.is_chunked() => {
+                Err(HttpError::NoChunkedCoding)
+            }
+            _ => Ok(()),
         }
+    }
 
-        let content_length = headers.content_length();
-        return Ok(true);
+    /// The transfer codings of every `Transfer-Encoding` field line, in
+    /// order
+    fn transfer_encoding(&self) -> Option<TransferEncoding> {
+        let headers = self.headers.as_ref().unwrap();
+        let value = headers.get_combined(TransferEncoding::NAME)?;
+        TransferEncoding::try_from(&value[..]).ok()
     }
 
-    fn is_chunked(&self) -> bool {
+    /// The `Content-Length`, which every field line has to agree on
+    fn content_length(&self) -> Result<Option<usize>, HttpError> {
         let headers = self.headers.as_ref().unwrap();
-        let transfer_encoding = headers.transfer_encoding();
+        let mut lengths = headers
+            .get_all(ContentLength::NAME)
+            .into_iter()
+            .filter_map(|h| h.as_any().downcast_ref::<ContentLength>())
+            .map(ContentLength::len);
+
+        let length = lengths.next();
+        if lengths.any(|l| Some(l) != length) {
+            return Err(HttpError::ConflictingContentLength);
+        }
 
-        transfer_encoding.is_some() && transfer_encoding.unwrap().is_chunked()
+        Ok(length)
     }
 
     fn are_headers_parsed(&self) -> bool {
@@ -296,13 +333,10 @@ impl RequestBuilder {
             return Ok(self);
         }
 
-        self.parse_request_line()?
-            .parse_headers()?
-            .create_request_body_builder();
+        self.parse_request_line()?.parse_headers()?;
 
         if self.are_headers_parsed() {
-            self.are_headers_valid()?;
-            self.create_request_body_builder();
+            self.create_request_body_builder()?;
             self.parse_body();
         }
 
@@ -335,6 +369,8 @@ impl RequestBuilder {
 #[cfg(test)]
 mod request_tests {
     use super::{HttpMethods, HttpVersion, RequestBuilder};
+    use crate::errors::Error as HttpError;
+    use crate::request::Request;
 
     #[test]
     fn test_one_pass_parse() {
@@ -427,4 +463,143 @@ mod request_tests {
         assert!(builder.http_version.is_some());
         assert!(builder.headers.is_some());
     }
+
+    #[test]
+    fn test_target_form_for_method() {
+        let valid: [&[u8]; 3] = [
+            b"CONNECT example.com:443 HTTP/1.1\r\n",
+            b"OPTIONS * HTTP/1.1\r\n",
+            b"GET http://example.com/a HTTP/1.1\r\n",
+        ];
+
+        for buffer in valid.iter() {
+            let mut builder = RequestBuilder::new();
+            assert!(builder.parse(buffer.to_vec(), buffer.len()).is_ok());
+            assert!(builder.uri.is_some());
+        }
+
+        let invalid: [&[u8]; 4] = [
+            b"GET example.com:443 HTTP/1.1\r\n",
+            b"GET * HTTP/1.1\r\n",
+            b"CONNECT /path HTTP/1.1\r\n",
+            b"GET /a#b HTTP/1.1\r\n",
+        ];
+
+        for buffer in invalid.iter() {
+            let mut builder = RequestBuilder::new();
+            let result = builder.parse(buffer.to_vec(), buffer.len());
+            assert_match_error!(result.err().unwrap(), HttpError::InvalidUri);
+        }
+    }
+
+    #[test]
+    fn test_built_request_accessors() {
+        let buffer =
+            b"POST /items?id=7 HTTP/1.1\r\nContent-Length: 3\r\n\r\nabc";
+        let mut builder = RequestBuilder::new();
+        builder.parse(buffer.to_vec(), buffer.len()).unwrap();
+        assert!(!builder.can_parse_more());
+
+        let mut request = builder.build();
+        assert_eq!(request.method(), &HttpMethods::POST);
+        assert_eq!(request.http_version(), &HttpVersion::Http11);
+        assert_eq!(request.uri().path(), "/items");
+        assert_eq!(request.uri().query(), Some("id=7"));
+        assert_eq!(request.headers().content_length().unwrap().len(), 3);
+        assert_eq!(request.body().unwrap().to_vec(), b"abc".to_vec());
+
+        let body = request.take_body();
+        assert_eq!(body.unwrap().to_vec(), b"abc".to_vec());
+        assert!(request.body().is_none());
+    }
+
+    fn parse(buffer: &[u8]) -> Result<RequestBuilder, HttpError> {
+        let mut builder = RequestBuilder::new();
+        builder.parse(buffer.to_vec(), buffer.len())?;
+        Ok(builder)
+    }
+
+    #[test]
+    fn test_repeated_content_length() {
+        let buffer = b"POST /a HTTP/1.1\r\nContent-Length: 3\r\n\
+                       Content-Length: 3\r\n\r\nabc";
+        let request = parse(buffer).unwrap().build();
+        assert_eq!(request.body().unwrap().to_vec(), b"abc".to_vec());
+
+        let buffer = b"POST /a HTTP/1.1\r\nContent-Length: 5\r\n\
+                       Content-Length: 50\r\n\r\nabcde";
+        assert_match_error!(
+            parse(buffer).err().unwrap(),
+            HttpError::ConflictingContentLength
+        );
+    }
+
+    #[test]
+    fn test_transfer_encoding_lines_are_combined() {
+        let buffer = b"POST /a HTTP/1.1\r\nTransfer-Encoding: gzip\r\n\
+                       Transfer-Encoding: Chunked\r\n\r\n\
+                       3\r\nabc\r\n0\r\n\r\n";
+        let builder = parse(buffer).unwrap();
+        assert!(builder.transfer_encoding().unwrap().is_chunked());
+        assert!(!builder.can_parse_more());
+
+        let buffer = b"POST /a HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\
+                       Transfer-Encoding: gzip\r\n\r\n";
+        assert_match_error!(
+            parse(buffer).err().unwrap(),
+            HttpError::NoChunkedCoding
+        );
+
+        let buffer = b"POST /a HTTP/1.1\r\nTransfer-Encoding: gzip\r\n\r\n";
+        assert_match_error!(
+            parse(buffer).err().unwrap(),
+            HttpError::NoChunkedCoding
+        );
+    }
+
+    #[test]
+    fn test_transfer_encoding_with_content_length() {
+        let buffer = b"POST /a HTTP/1.1\r\nContent-Length: 5\r\n\
+                       Transfer-Encoding: chunked\r\n\r\n0\r\n\r\n";
+        assert_match_error!(
+            parse(buffer).err().unwrap(),
+            HttpError::ContentLengthWithTransferEncoding
+        );
+    }
+
+    #[test]
+    fn test_empty_framing_headers() {
+        let buffer = b"POST /a HTTP/1.1\r\nTransfer-Encoding:\r\n\r\n0\r\n\r\n";
+        assert_match_error!(
+            parse(buffer).err().unwrap(),
+            HttpError::NoChunkedCoding
+        );
+
+        let buffer = b"POST /a HTTP/1.1\r\nContent-Length:\r\n\r\n";
+        assert!(parse(buffer).is_err());
+    }
+
+    #[test]
+    fn test_request_is_send() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Request>();
+
+        let buffer = b"GET /a HTTP/1.1\r\nX-Id: 1\r\n\r\n";
+        let mut builder = RequestBuilder::new();
+        builder.parse(buffer.to_vec(), buffer.len()).unwrap();
+        let request = builder.build();
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .build()
+            .unwrap();
+        let path = runtime.block_on(async move {
+            let task = tokio::spawn(async move {
+                tokio::task::yield_now().await;
+                request.uri().path().to_string()
+            });
+            task.await.unwrap()
+        });
+        assert_eq!(path, "/a");
+    }
 }
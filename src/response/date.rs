@@ -0,0 +1,60 @@
+use crate::grammar::format_http_date;
+use std::cell::RefCell;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+struct CachedDate {
+    second: u64,
+    value: String,
+}
+
+thread_local! {
+    // per thread, so workers never wait on each other for the date
+    static CACHED_DATE: RefCell<CachedDate> = const {
+        RefCell::new(CachedDate {
+            second: u64::MAX,
+            value: String::new(),
+        })
+    };
+}
+
+/// Calls `f` with the current time as an IMF-fixdate. The string is
+/// formatted at most once per second on each thread.
+pub(crate) fn with_http_date<R>(f: impl FnOnce(&str) -> R) -> R {
+    with_http_date_at(SystemTime::now(), f)
+}
+
+fn with_http_date_at<R>(now: SystemTime, f: impl FnOnce(&str) -> R) -> R {
+    let second = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+
+    CACHED_DATE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.second != second {
+            cache.second = second;
+            cache.value = format_http_date(now);
+        }
+
+        f(&cache.value)
+    })
+}
+
+#[cfg(test)]
+mod tests_date {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_cached_date() {
+        let now = UNIX_EPOCH + Duration::from_secs(784111777);
+        let (date, ptr) =
+            with_http_date_at(now, |d| (d.to_string(), d.as_ptr()));
+        assert_eq!(date, "Sun, 06 Nov 1994 08:49:37 GMT");
+
+        // within the same second the cached string is handed out again
+        let later = now + Duration::from_millis(900);
+        assert_eq!(with_http_date_at(later, |d| d.as_ptr()), ptr);
+
+        let next = now + Duration::from_secs(1);
+        let date = with_http_date_at(next, |d| d.to_string());
+        assert_eq!(date, "Sun, 06 Nov 1994 08:49:38 GMT");
+    }
+}
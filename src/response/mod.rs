@@ -1,8 +1,24 @@
+use crate::extensions::Extensions;
+use crate::headers::{
+    CacheControl, CacheDirective, ContentLength, Headers, SetCookie,
+    TypedHeader,
+};
 use crate::status::StatusCode;
+use std::io;
+use std::time::Duration;
+
+mod date;
+mod ranges;
+
+static DATE_HEADER_NAME: &str = "date";
 
 pub struct Response {
     is_response_ready: bool,
     is_sent: bool,
+    status: StatusCode,
+    headers: Headers,
+    body: Vec<u8>,
+    extensions: Extensions,
 }
 
 impl Response {
@@ -10,8 +26,233 @@ impl Response {
         Response {
             is_sent: false,
             is_response_ready: false,
+            status: StatusCode::OK,
+            headers: Headers::new(),
+            body: vec![],
+            extensions: Extensions::new(),
         }
     }
 
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn set_status(&mut self, status: StatusCode) {
+        self.status = status;
+    }
+
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    pub fn headers_mut(&mut self) -> &mut Headers {
+        &mut self.headers
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Sets the message body and its `Content-Length`
+    pub fn set_body(&mut self, body: Vec<u8>) {
+        self.headers.insert_typed(ContentLength::new(body.len()));
+        self.body = body;
+    }
+
+    /// Adds a `Set-Cookie` header line, keeping cookies added before
+    pub fn add_cookie(&mut self, cookie: SetCookie) {
+        self.headers.append_typed(cookie);
+    }
+
+    /// Sets the `Cache-Control` header, replacing an earlier one
+    pub fn set_cache_control(&mut self, cache_control: CacheControl) {
+        self.headers.insert_typed(cache_control);
+    }
+
+    /// For versioned assets whose content never changes:
+    /// `public, max-age=..., immutable`
+    pub fn cache_immutable(&mut self, max_age: Duration) {
+        self.set_cache_control(
+            CacheControl::new()
+                .with(CacheDirective::Public)
+                .with(CacheDirective::MaxAge(max_age))
+                .with(CacheDirective::Immutable),
+        );
+    }
+
+    /// For personal data which no cache may keep: `private, no-store`
+    pub fn cache_private_no_store(&mut self) {
+        self.set_cache_control(
+            CacheControl::new()
+                .with(CacheDirective::Private(vec![]))
+                .with(CacheDirective::NoStore),
+        );
+    }
+
+    /// Fresh for `max_age`, then served stale for up to `stale` more while
+    /// caches revalidate it in the background, RFC 5861
+    pub fn cache_stale_while_revalidate(
+        &mut self,
+        max_age: Duration,
+        stale: Duration,
+    ) {
+        self.set_cache_control(
+            CacheControl::new()
+                .with(CacheDirective::MaxAge(max_age))
+                .with(CacheDirective::StaleWhileRevalidate(stale)),
+        );
+    }
+
+    /// Data attached to the response by handlers and middleware
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
     pub fn send_code(&mut self, code: StatusCode) {}
+
+    /// Writes the status line and the header section. A `Date` header is
+    /// added unless the handler set one, RFC 7231 section 7.1.1.2, and a
+    /// `Content-Length` for the body unless the status has none, so that
+    /// the next response on the connection can be told apart.
+    pub(crate) fn write_head<W: io::Write>(
+        &self,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        let code = self.status.as_u16();
+        let reason = StatusCode::reason(code).unwrap_or("");
+        write!(writer, "HTTP/1.1 {} {}\r\n", code, reason)?;
+
+        if !self.headers.contains(DATE_HEADER_NAME) {
+            date::with_http_date(|date| {
+                write!(writer, "{}: {}\r\n", DATE_HEADER_NAME, date)
+            })?;
+        }
+
+        if self.status_allows_body()
+            && !self.headers.contains(ContentLength::NAME)
+        {
+            write!(writer, "{}: {}\r\n", ContentLength::NAME, self.body.len())?;
+        }
+
+        self.headers.write_to(writer)?;
+        writer.write_all(b"\r\n")
+    }
+
+    /// Whether a response with this status can have a body, which `1xx`,
+    /// `204 No Content` and `304 Not Modified` responses never have, RFC
+    /// 7230 section 3.3.3
+    pub(crate) fn status_allows_body(&self) -> bool {
+        let code = self.status.as_u16();
+        !(100..200).contains(&code)
+            && self.status != StatusCode::NO_CONTENT
+            && self.status != StatusCode::NOT_MODIFIED
+    }
+}
+
+#[cfg(test)]
+mod tests_response {
+    use super::Response;
+    use crate::grammar::parse_http_date;
+    use crate::headers::SetCookie;
+    use crate::status::StatusCode;
+    use std::str;
+    use std::time::Duration;
+
+    #[test]
+    fn test_add_cookie() {
+        let mut response = Response::new();
+        response
+            .headers_mut()
+            .insert("Content-Length", "0")
+            .unwrap();
+
+        let cookie = SetCookie::builder("a", "1").path("/").build().unwrap();
+        response.add_cookie(cookie);
+        let cookie = SetCookie::builder("b", "2").http_only(true).build();
+        response.add_cookie(cookie.unwrap());
+
+        let mut buffer = vec![];
+        response.headers().write_to(&mut buffer).unwrap();
+        assert_eq!(
+            str::from_utf8(&buffer).unwrap(),
+            "content-length: 0\r\nset-cookie: a=1; Path=/\r\n\
+             set-cookie: b=2; HttpOnly\r\n"
+        );
+    }
+
+    #[test]
+    fn test_write_head() {
+        let mut response = Response::new();
+        response
+            .headers_mut()
+            .insert("Content-Length", "0")
+            .unwrap();
+
+        let mut buffer = vec![];
+        response.write_head(&mut buffer).unwrap();
+        let head = str::from_utf8(&buffer).unwrap();
+        let lines: Vec<&str> = head.split("\r\n").collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], "HTTP/1.1 200 OK");
+        let date = lines[1].strip_prefix("date: ").unwrap();
+        assert!(parse_http_date(date).is_some());
+        assert_eq!(lines[2..], ["content-length: 0", "", ""]);
+    }
+
+    #[test]
+    fn test_write_head_keeps_date() {
+        let mut response = Response::new();
+        response.set_status(StatusCode::NOT_FOUND);
+        response
+            .headers_mut()
+            .insert("Date", "Sun, 06 Nov 1994 08:49:37 GMT")
+            .unwrap();
+
+        let mut buffer = vec![];
+        response.write_head(&mut buffer).unwrap();
+        assert_eq!(
+            str::from_utf8(&buffer).unwrap(),
+            "HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\n\
+             date: Sun, 06 Nov 1994 08:49:37 GMT\r\n\r\n"
+        );
+
+        // responses which never have a body get no Content-Length
+        response.set_status(StatusCode::NOT_MODIFIED);
+        let mut buffer = vec![];
+        response.write_head(&mut buffer).unwrap();
+        assert_eq!(
+            str::from_utf8(&buffer).unwrap(),
+            "HTTP/1.1 304 Not Modified\r\n\
+             date: Sun, 06 Nov 1994 08:49:37 GMT\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_cache_helpers() {
+        let mut response = Response::new();
+        let cache_control = |response: &Response| {
+            response.headers().get("cache-control").unwrap().value()
+        };
+
+        response.cache_immutable(Duration::from_secs(31536000));
+        assert_eq!(
+            cache_control(&response),
+            "public, max-age=31536000, immutable"
+        );
+
+        response.cache_private_no_store();
+        assert_eq!(cache_control(&response), "private, no-store");
+
+        let max_age = Duration::from_secs(60);
+        response.cache_stale_while_revalidate(max_age, max_age * 10);
+        assert_eq!(
+            cache_control(&response),
+            "max-age=60, stale-while-revalidate=600"
+        );
+        assert_eq!(response.headers().get_all("cache-control").len(), 1);
+    }
 }
@@ -0,0 +1,261 @@
+use crate::headers::{
+    ContentRange, ContentType, Range, TypedHeader, Validators,
+};
+use crate::request::{HttpMethods, Request};
+use crate::response::Response;
+use crate::status::StatusCode;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+static ACCEPT_RANGES_HEADER_NAME: &str = "accept-ranges";
+
+impl Response {
+    /// Sets `body`, the complete selected representation, as the body, or
+    /// the parts of it the `Range` header of a `GET` request asks for,
+    /// RFC 7233.
+    ///
+    /// A single range is sent as `206 Partial Content` with a
+    /// `Content-Range`, several as `multipart/byteranges` with the
+    /// `Content-Type` set before as type of each part. If no range is
+    /// satisfiable, the status is `416 Range Not Satisfiable`. The `Range`
+    /// header is ignored if it is invalid or in another unit than bytes, or
+    /// if the `If-Range` condition does not match `validators`.
+    pub fn set_ranged_body(
+        &mut self,
+        request: &Request,
+        validators: &Validators,
+        body: Vec<u8>,
+    ) {
+        // the value is a valid token
+        self.headers
+            .insert(ACCEPT_RANGES_HEADER_NAME, "bytes")
+            .unwrap();
+
+        let headers = request.headers();
+        let if_range_matches = match headers.if_range() {
+            Some(if_range) => if_range.matches(validators),
+            None => true,
+        };
+        let range = match headers.range() {
+            Some(range @ Range::Bytes(_))
+                if request.method() == &HttpMethods::GET
+                    && if_range_matches =>
+            {
+                range
+            }
+            _ => return self.set_body(body),
+        };
+
+        let len = body.len() as u64;
+        let ranges = coalesce(range.satisfiable(len));
+        match ranges[..] {
+            [] => {
+                self.status = StatusCode::RANGE_NOT_SATISFIABLE;
+                self.headers.insert_typed(ContentRange::unsatisfied(len));
+                self.set_body(vec![]);
+            }
+            [(first, last)] => {
+                let content_range = content_range(first, last, len);
+                self.status = StatusCode::PARTIAL_CONTENT;
+                self.headers.insert_typed(content_range);
+                self.set_body(body[first as usize..=last as usize].to_vec());
+            }
+            _ => {
+                self.status = StatusCode::PARTIAL_CONTENT;
+                self.set_multipart_body(&ranges, body);
+            }
+        }
+    }
+
+    fn set_multipart_body(&mut self, ranges: &[(u64, u64)], body: Vec<u8>) {
+        let boundary = new_boundary();
+        let part_type = self.headers.content_type().map(|t| t.encode());
+        let len = body.len() as u64;
+
+        let mut multipart = vec![];
+        for (first, last) in ranges.iter().copied() {
+            multipart
+                .extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+            if let Some(part_type) = &part_type {
+                let line = format!("{}: {}\r\n", ContentType::NAME, part_type);
+                multipart.extend_from_slice(line.as_bytes());
+            }
+
+            let content_range = content_range(first, last, len);
+            let line =
+                format!("{}: {}\r\n\r\n", ContentRange::NAME, content_range);
+            multipart.extend_from_slice(line.as_bytes());
+            multipart.extend_from_slice(&body[first as usize..=last as usize]);
+            multipart.extend_from_slice(b"\r\n");
+        }
+        multipart.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+
+        let content_type = ContentType::new("multipart", "byteranges")
+            .with_param("boundary", &boundary);
+        self.headers.insert_typed(content_type);
+        self.set_body(multipart);
+    }
+}
+
+fn content_range(first: u64, last: u64, len: u64) -> ContentRange {
+    // satisfiable ranges always lie within the representation
+    ContentRange::bytes(first, last, Some(len)).unwrap()
+}
+
+/// Merges overlapping and adjacent ranges, so a client asking for many
+/// small or repeated ranges cannot make the response larger than the
+/// representation, RFC 7233 section 6.1
+fn coalesce(mut ranges: Vec<(u64, u64)>) -> Vec<(u64, u64)> {
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(u64, u64)> = vec![];
+    for (first, last) in ranges {
+        match merged.last_mut() {
+            Some(previous) if first <= previous.1 + 1 => {
+                previous.1 = previous.1.max(last);
+            }
+            _ => merged.push((first, last)),
+        }
+    }
+
+    merged
+}
+
+/// A boundary which is unique per response, without depending on a random
+/// number generator
+fn new_boundary() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64);
+    format!("http11-{:016x}{:08x}", nanos, count)
+}
+
+#[cfg(test)]
+mod tests_ranges {
+    use super::*;
+    use crate::headers::{ContentLength, EntityTag};
+    use crate::request::RequestBuilder;
+    use std::str;
+
+    const BODY: &[u8] = b"0123456789abcdefghij";
+
+    fn request(method: &str, headers: &str) -> Request {
+        let buffer =
+            format!("{} /file HTTP/1.1\r\n{}\r\n\r\n", method, headers);
+        let mut builder = RequestBuilder::new();
+        builder
+            .parse(buffer.as_bytes().to_vec(), buffer.len())
+            .unwrap();
+        builder.build()
+    }
+
+    fn respond(method: &str, headers: &str) -> Response {
+        let validators =
+            Validators::new().with_etag(EntityTag::strong("v1").unwrap());
+        let mut response = Response::new();
+        response
+            .headers_mut()
+            .insert("Content-Type", "text/plain")
+            .unwrap();
+        response.set_ranged_body(
+            &request(method, headers),
+            &validators,
+            BODY.to_vec(),
+        );
+        response
+    }
+
+    fn header(response: &Response, name: &str) -> Option<String> {
+        response.headers().get(name).map(|h| h.value())
+    }
+
+    #[test]
+    fn test_full_body() {
+        for (method, headers) in [
+            ("GET", "X-Id: 1"),
+            ("HEAD", "Range: bytes=0-4"),
+            ("GET", "Range: items=0-4"),
+            ("GET", "Range: bytes=0-4\r\nIf-Range: \"v2\""),
+            ("GET", "Range: bytes=0-4\r\nIf-Range: W/\"v1\""),
+            ("GET", "Range: bytes=0-4\r\nIf-Range: yesterday"),
+            ("GET", "Range: 0-4"),
+            ("GET", "Range: bytes=5-1"),
+            ("GET", "Range: bytes=0-4, 5-1"),
+            ("POST", "Range: bytes=0-4"),
+        ] {
+            let response = respond(method, headers);
+            assert_eq!(response.status(), StatusCode::OK, "{}", headers);
+            assert_eq!(response.body(), BODY);
+            assert_eq!(header(&response, "content-length").unwrap(), "20");
+            assert_eq!(header(&response, "accept-ranges").unwrap(), "bytes");
+            assert!(header(&response, "content-range").is_none());
+        }
+    }
+
+    #[test]
+    fn test_single_range() {
+        let response = respond("GET", "Range: bytes=-5\r\nIf-Range: \"v1\"");
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.body(), b"fghij");
+        assert_eq!(
+            header(&response, "content-range").unwrap(),
+            "bytes 15-19/20"
+        );
+        assert_eq!(header(&response, "content-length").unwrap(), "5");
+        assert_eq!(header(&response, "content-type").unwrap(), "text/plain");
+
+        // overlapping ranges are merged into one
+        let response = respond("GET", "Range: bytes=2-5, 4-7, 30-");
+        assert_eq!(response.body(), b"234567");
+        assert_eq!(header(&response, "content-range").unwrap(), "bytes 2-7/20");
+    }
+
+    #[test]
+    fn test_multiple_ranges() {
+        let response = respond("GET", "Range: bytes=10-12, 0-1");
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert!(header(&response, "content-range").is_none());
+
+        let content_type = response.headers().content_type().unwrap();
+        assert!(content_type.is("multipart/byteranges"));
+        let boundary = content_type.boundary().unwrap();
+
+        let expected = format!(
+            "--{b}\r\ncontent-type: text/plain\r\n\
+             content-range: bytes 0-1/20\r\n\r\n01\r\n\
+             --{b}\r\ncontent-type: text/plain\r\n\
+             content-range: bytes 10-12/20\r\n\r\nabc\r\n--{b}--\r\n",
+            b = boundary
+        );
+        assert_eq!(str::from_utf8(response.body()).unwrap(), expected);
+        let length = response.headers().get_typed::<ContentLength>();
+        assert_eq!(length.unwrap().len(), expected.len());
+    }
+
+    #[test]
+    fn test_not_satisfiable() {
+        for headers in ["Range: bytes=20-, -0", "Range: bytes=30-"] {
+            let response = respond("GET", headers);
+            assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+            assert!(response.body().is_empty());
+            assert_eq!(
+                header(&response, "content-range").unwrap(),
+                "bytes */20"
+            );
+            assert_eq!(header(&response, "content-length").unwrap(), "0");
+        }
+    }
+
+    #[test]
+    fn test_coalesce() {
+        assert_eq!(coalesce(vec![]), vec![]);
+        assert_eq!(
+            coalesce(vec![(5, 6), (0, 1), (2, 3), (5, 9), (11, 12)]),
+            vec![(0, 3), (5, 9), (11, 12)]
+        );
+        assert_ne!(new_boundary(), new_boundary());
+    }
+}
@@ -0,0 +1,147 @@
+use socket2::{SockRef, TcpKeepalive};
+use std::io::Result;
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+static DEFAULT_LISTEN_BACKLOG: u32 = 1024;
+
+/// Socket level settings for the listeners and for every accepted
+/// connection. `None` keeps the operating system default.
+#[derive(Clone, Debug)]
+pub(crate) struct SocketOptions {
+    pub(crate) listen_backlog: u32,
+    pub(crate) nodelay: Option<bool>,
+    pub(crate) keepalive_time: Option<Duration>,
+    pub(crate) keepalive_interval: Option<Duration>,
+    pub(crate) keepalive_retries: Option<u32>,
+    pub(crate) send_buffer_size: Option<usize>,
+    pub(crate) recv_buffer_size: Option<usize>,
+}
+
+impl SocketOptions {
+    pub(crate) fn new() -> SocketOptions {
+        SocketOptions {
+            listen_backlog: DEFAULT_LISTEN_BACKLOG,
+            nodelay: None,
+            keepalive_time: None,
+            keepalive_interval: None,
+            keepalive_retries: None,
+            send_buffer_size: None,
+            recv_buffer_size: None,
+        }
+    }
+
+    /// Keepalive is enabled as soon as any of its parameters is set
+    fn keepalive(&self) -> Option<TcpKeepalive> {
+        if self.keepalive_time.is_none()
+            && self.keepalive_interval.is_none()
+            && self.keepalive_retries.is_none()
+        {
+            return None;
+        }
+
+        let mut keepalive = TcpKeepalive::new();
+        if let Some(time) = self.keepalive_time {
+            keepalive = keepalive.with_time(time);
+        }
+
+        #[cfg(any(target_os = "linux", target_os = "macos", windows))]
+        if let Some(interval) = self.keepalive_interval {
+            keepalive = keepalive.with_interval(interval);
+        }
+
+        #[cfg(any(target_os = "linux", target_os = "macos", windows))]
+        if let Some(retries) = self.keepalive_retries {
+            keepalive = keepalive.with_retries(retries);
+        }
+
+        Some(keepalive)
+    }
+
+    pub(crate) fn apply(&self, stream: &TcpStream) -> Result<()> {
+        let socket = SockRef::from(stream);
+
+        if let Some(nodelay) = self.nodelay {
+            socket.set_tcp_nodelay(nodelay)?;
+        }
+
+        if let Some(keepalive) = self.keepalive() {
+            socket.set_tcp_keepalive(&keepalive)?;
+        }
+
+        if let Some(size) = self.send_buffer_size {
+            socket.set_send_buffer_size(size)?;
+        }
+
+        if let Some(size) = self.recv_buffer_size {
+            socket.set_recv_buffer_size(size)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for SocketOptions {
+    fn default() -> Self {
+        SocketOptions::new()
+    }
+}
+
+#[cfg(test)]
+mod tests_socket_options {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    async fn connected_stream() -> TcpStream {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let client = TcpStream::connect(address);
+        let (client, accepted) = tokio::join!(client, listener.accept());
+        drop(client);
+        accepted.unwrap().0
+    }
+
+    #[tokio::test]
+    async fn test_default_keeps_socket_untouched() {
+        let stream = connected_stream().await;
+        let socket = SockRef::from(&stream);
+        let nodelay = socket.tcp_nodelay().unwrap();
+        let keepalive = socket.keepalive().unwrap();
+
+        SocketOptions::new().apply(&stream).unwrap();
+        assert_eq!(socket.tcp_nodelay().unwrap(), nodelay);
+        assert_eq!(socket.keepalive().unwrap(), keepalive);
+    }
+
+    #[tokio::test]
+    async fn test_apply() {
+        let stream = connected_stream().await;
+        let mut options = SocketOptions::new();
+        options.nodelay = Some(true);
+        options.send_buffer_size = Some(64 * 1024);
+
+        options.apply(&stream).unwrap();
+
+        let socket = SockRef::from(&stream);
+        assert!(socket.tcp_nodelay().unwrap());
+        assert!(socket.send_buffer_size().unwrap() >= 64 * 1024);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_apply_keepalive_probes() {
+        let stream = connected_stream().await;
+        let mut options = SocketOptions::new();
+        options.keepalive_time = Some(Duration::from_secs(60));
+        options.keepalive_interval = Some(Duration::from_secs(5));
+        options.keepalive_retries = Some(3);
+
+        options.apply(&stream).unwrap();
+
+        let socket = SockRef::from(&stream);
+        assert!(socket.keepalive().unwrap());
+        assert_eq!(socket.tcp_keepalive_time().unwrap().as_secs(), 60);
+        assert_eq!(socket.tcp_keepalive_interval().unwrap().as_secs(), 5);
+        assert_eq!(socket.tcp_keepalive_retries().unwrap(), 3);
+    }
+}
@@ -1,11 +1,15 @@
 use std::num::NonZeroU16;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct StatusCode(NonZeroU16);
 
 pub struct InvalidStatusCode();
 
 impl StatusCode {
+    pub fn as_u16(&self) -> u16 {
+        self.0.get()
+    }
+
     fn from_u16(code: u16) -> Result<StatusCode, InvalidStatusCode> {
         if code < 100 || code >= 1000 {
             return Err(InvalidStatusCode());
@@ -45,7 +49,12 @@ macro_rules! status_code {
 status_code! {
     (100, CONTINUE, "Continue");
     (200, OK, "OK");
+    (204, NO_CONTENT, "No Content");
+    (206, PARTIAL_CONTENT, "Partial Content");
+    (304, NOT_MODIFIED, "Not Modified");
     (400, BAD_REQUEST, "Bad Request");
     (404, NOT_FOUND, "Not Found");
+    (412, PRECONDITION_FAILED, "Precondition Failed");
+    (416, RANGE_NOT_SATISFIABLE, "Range Not Satisfiable");
     (500, INTERNAL_SERVER_ERROR, "Internal Server Error");
 }